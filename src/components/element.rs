@@ -1,16 +1,23 @@
-use bevy::prelude::*;
-use rand::Rng;
+use crate::simulation::AMBIENT_TEMPERATURE;
+use serde::Deserialize;
+use std::ops::RangeInclusive;
 
-#[derive(Component, Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
 pub enum ElementType {
     Liquid,
     MovableSolid,
     ImmovableSolid,
     Gas,
+    /// Stays put and keeps creating particles around it, see [`Flow`](crate::simulation::Flow).
+    Source,
+    /// Stays put and deletes the particles touching it, see [`Flow`](crate::simulation::Flow).
+    Sink,
     Erase,
+    Heat,
+    Cool,
 }
 
-#[derive(Component, Clone, Copy)]
+#[derive(Clone, Copy, Deserialize)]
 pub struct ColorValue {
     pub r: f32,
     pub g: f32,
@@ -21,83 +28,134 @@ impl ColorValue {
     pub fn new(r: f32, g: f32, b: f32) -> Self {
         ColorValue { r, g, b }
     }
+}
+
+/// Temperature at which a [`PhaseTransition`] happens.
+#[derive(Clone, Copy, Deserialize)]
+pub enum Threshold {
+    Below(f32),
+    Above(f32),
+}
 
-    pub fn to_bevy_color(&self) -> Color {
-        Color::srgb(self.r, self.g, self.b)
+impl Threshold {
+    pub fn is_crossed_by(&self, temperature: f32) -> bool {
+        match *self {
+            Threshold::Below(threshold) => temperature < threshold,
+            Threshold::Above(threshold) => temperature > threshold,
+        }
     }
 }
 
-#[derive(Component, Clone)]
+/// Turns a particle into another element once its temperature crosses a threshold.
+#[derive(Clone, Deserialize)]
+pub struct PhaseTransition {
+    pub threshold: Threshold,
+    pub into: String,
+}
+
+/// How a flammable element catches fire and burns.
+#[derive(Clone, Deserialize)]
+pub struct Combustion {
+    /// Chance per tick of catching fire from each burning neighbour.
+    pub flammability: f64,
+    /// Temperature at which the particle catches fire on its own.
+    pub ignition_temperature: f32,
+    /// Ticks the particle burns before it is used up.
+    pub burn_duration: u32,
+    /// What is left once the particle has burnt out; `None` leaves nothing.
+    #[serde(default)]
+    pub burns_into: Option<String>,
+    /// What the burning particle now and then gives off as flames into the empty cell above
+    /// it; `None` gives off none.
+    #[serde(default)]
+    pub emits_flame: Option<String>,
+    /// What the burning particle more rarely gives off as smoke into the empty cell above it;
+    /// `None` gives off none.
+    #[serde(default)]
+    pub emits_smoke: Option<String>,
+}
+
+/// How much of a solid structure can hang off the element before it gives way.
+#[derive(Clone, Deserialize)]
+pub struct Structure {
+    /// Furthest a particle may be from support, counted in cells sideways or upwards through
+    /// connected solids, before it crumbles. Cells resting on others add nothing.
+    pub strength: u16,
+    /// What a particle crumbles into; `None` leaves nothing.
+    #[serde(default)]
+    pub crumbles_into: Option<String>,
+}
+
+#[derive(Clone, Deserialize)]
 pub struct Element {
     pub element_type: ElementType,
+    #[serde(rename = "name")]
     pub element: String,
     pub mass: f32,
+    /// How quickly sliding particles stop, from 0 to 1.
     pub friction: f32,
+    /// For liquids, how many cells a particle flows sideways per tick, with any fraction being
+    /// the chance of one more. For gases, how rarely a boxed-in particle sinks, from 0 to 100.
     pub dispersion_rate: f32,
+    /// How readily heat flows between this element and its neighbours, from 0 to 1.
+    pub thermal_conductivity: f32,
+    /// How much heat it takes to change the temperature; higher values warm and cool slower.
+    pub heat_capacity: f32,
+    /// Temperature newly placed particles start at.
+    #[serde(default = "ambient_temperature")]
+    pub temperature: f32,
+    #[serde(default)]
+    pub transitions: Vec<PhaseTransition>,
+    /// `None` for elements that never burn.
+    #[serde(default)]
+    pub combustion: Option<Combustion>,
+    /// Range the lifetime of each particle is picked from, in ticks; `None` lives forever.
+    #[serde(default)]
+    pub lifetime: Option<RangeInclusive<u32>>,
+    /// What a particle turns into once its lifetime runs out; `None` leaves nothing.
+    #[serde(default)]
+    pub decays_into: Option<String>,
+    /// For immovable solids, how far they can span unsupported; `None` holds any load.
+    #[serde(default)]
+    pub structure: Option<Structure>,
     pub color: ColorValue,
 }
 
 impl Element {
-    pub fn new(element: String) -> Self {
-        match element.as_str() {
-            "Water" => Element {
-                element_type: ElementType::Liquid,
-                element,
-                mass: 1.,
-                friction: 0.5,
-                dispersion_rate: 5.,
-                color: ColorValue::new(0.0, 0.0, 1.0),
-            },
-            "Smoke" => Element {
-                element_type: ElementType::Gas,
-                element,
-                mass: 0.01,
-                friction: 0.1,
-                dispersion_rate: 20.,
-                color: ColorValue::new(0.5, 0.5, 0.5),
-            },
-            "Sand" => Element {
-                element_type: ElementType::MovableSolid,
-                element,
-                mass: 1.5,
-                friction: 0.5,
-                dispersion_rate: 5.,
-                color: ColorValue::new(1.0, 1.0, 0.6),
-            },
-            "Stone" => Element {
-                element_type: ElementType::ImmovableSolid,
-                element,
-                mass: 5.0,
-                friction: 5.0,
-                dispersion_rate: 0.,
-                color: ColorValue::new(0.6, 0.6, 0.6),
-            },
-            "Erase" => Element {
-                element_type: ElementType::Erase,
-                element,
-                mass: 0.0,
-                friction: 0.0,
-                dispersion_rate: 0.0,
-                color: ColorValue::new(1.0, 0.0, 0.0), // Changed to red for visibility
-            },
-            _ => Element {
-                element_type: ElementType::ImmovableSolid,
-                element,
-                mass: 1.,
-                friction: 0.5,
-                dispersion_rate: 5.,
-                color: ColorValue::new(0.0, 0.0, 0.0),
-            },
+    /// Chance per tick that this element sinks into `other` by trading places with it. Only
+    /// liquids and gases lighter than this element can be displaced.
+    pub fn displacement_chance(&self, other: &Element) -> f64 {
+        match other.element_type {
+            ElementType::Liquid | ElementType::Gas if other.mass < self.mass => {
+                ((self.mass - other.mass) / self.mass) as f64
+            }
+            _ => 0.0,
         }
     }
 
-    pub fn get_color_with_random_alpha(&self) -> Color {
-        let mut rng = rand::thread_rng();
-        let random_alpha = rng.gen_range(0.5..=1.0);
-        Color::srgba(self.color.r, self.color.g, self.color.b, random_alpha)
+    /// Colour as sRGB bytes with the given alpha, as stored in simulation cells.
+    pub fn color_bytes(&self, alpha_value: f32) -> [u8; 4] {
+        [self.color.r, self.color.g, self.color.b, alpha_value].map(|c| (c * 255.0).round() as u8)
     }
+}
 
-    pub fn get_color_with_alpha(&self, alpha_value: f32) -> Color {
-        Color::srgba(self.color.r, self.color.g, self.color.b, alpha_value)
-    }
+fn ambient_temperature() -> f32 {
+    AMBIENT_TEMPERATURE
+}
+
+/// What happens when two elements touch.
+#[derive(Clone, Deserialize)]
+pub struct Reaction {
+    /// The two elements that react when they are direct neighbours.
+    pub reactants: [String; 2],
+    /// What each reactant turns into, in the same order; `None` consumes it.
+    pub products: [Option<String>; 2],
+    /// Chance per tick that a touching pair reacts.
+    pub probability: f64,
+    /// Elements of which at least one must touch either reactant for the reaction to happen.
+    #[serde(default)]
+    pub catalysts: Vec<String>,
+    /// Extra particles released into empty cells around the reactants.
+    #[serde(default)]
+    pub byproducts: Vec<String>,
 }
@@ -0,0 +1,360 @@
+use crate::components::element::{Element, ElementType, Reaction, Threshold};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::{fmt, io};
+
+/// Definitions shipped with the game, used until the asset file has loaded and by headless
+/// simulations.
+const BUILT_IN: &str = include_str!("../../assets/elements.ron");
+
+/// Contents of an element definitions file: every element and the reactions between them.
+#[derive(Deserialize, Clone)]
+pub struct ElementDefinitions {
+    pub elements: Vec<Element>,
+    #[serde(default)]
+    pub reactions: Vec<Reaction>,
+}
+
+impl ElementDefinitions {
+    /// Parses definitions written in RON and checks that every element they refer to exists.
+    pub fn from_ron(source: &str) -> Result<Self, ElementError> {
+        let definitions: ElementDefinitions = ron::from_str(source).map_err(ElementError::Parse)?;
+        ElementRegistry::new(&definitions)?;
+        Ok(definitions)
+    }
+}
+
+impl Default for ElementDefinitions {
+    fn default() -> Self {
+        ElementDefinitions::from_ron(BUILT_IN).expect("built-in element definitions are invalid")
+    }
+}
+
+/// Compact handle to an element in an [`ElementRegistry`], stored by every particle instead of
+/// the element itself.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ElementId(pub u16);
+
+/// Elements an element can turn into, resolved to IDs.
+struct Links {
+    transitions: Vec<(Threshold, ElementId)>,
+    burns_into: Option<ElementId>,
+    emits_flame: Option<ElementId>,
+    emits_smoke: Option<ElementId>,
+    decays_into: Option<ElementId>,
+    crumbles_into: Option<ElementId>,
+}
+
+/// A [`Reaction`] with its element names resolved to IDs.
+pub struct ReactionRule {
+    pub reactants: [ElementId; 2],
+    pub products: [Option<ElementId>; 2],
+    pub probability: f64,
+    pub catalysts: Vec<ElementId>,
+    pub byproducts: Vec<ElementId>,
+}
+
+/// Every known element, numbered in the order they are defined, along with the reactions
+/// between them. Cheap to clone, as clones share the same definitions.
+#[derive(Clone)]
+pub struct ElementRegistry {
+    elements: Arc<[Element]>,
+    links: Arc<[Links]>,
+    ids: Arc<HashMap<String, ElementId>>,
+    /// Reactions indexed by the ID of their first reactant.
+    reactions: Arc<[Vec<ReactionRule>]>,
+}
+
+impl ElementRegistry {
+    /// Numbers the elements of `definitions`, resolves every name they refer to and checks that
+    /// every chance, rate and range they hold can be used as one.
+    pub fn new(definitions: &ElementDefinitions) -> Result<Self, ElementError> {
+        if definitions.elements.is_empty() {
+            return Err(ElementError::Empty);
+        }
+        for element in &definitions.elements {
+            check_ranges(element)?;
+        }
+        let mut ids = HashMap::new();
+        for (index, element) in definitions.elements.iter().enumerate() {
+            let id = ElementId(u16::try_from(index).map_err(|_| ElementError::TooMany)?);
+            if ids.insert(element.element.clone(), id).is_some() {
+                return Err(ElementError::Duplicate(element.element.clone()));
+            }
+        }
+        let resolve = |from: &str, name: &String| {
+            ids.get(name)
+                .copied()
+                .ok_or_else(|| ElementError::UnknownReference {
+                    from: from.to_string(),
+                    name: name.clone(),
+                })
+        };
+
+        let links = definitions
+            .elements
+            .iter()
+            .map(|element| {
+                let from = element.element.as_str();
+                Ok(Links {
+                    transitions: element
+                        .transitions
+                        .iter()
+                        .map(|transition| {
+                            Ok((transition.threshold, resolve(from, &transition.into)?))
+                        })
+                        .collect::<Result<_, ElementError>>()?,
+                    burns_into: element
+                        .combustion
+                        .as_ref()
+                        .and_then(|combustion| combustion.burns_into.as_ref())
+                        .map(|name| resolve(from, name))
+                        .transpose()?,
+                    emits_flame: element
+                        .combustion
+                        .as_ref()
+                        .and_then(|combustion| combustion.emits_flame.as_ref())
+                        .map(|name| resolve(from, name))
+                        .transpose()?,
+                    emits_smoke: element
+                        .combustion
+                        .as_ref()
+                        .and_then(|combustion| combustion.emits_smoke.as_ref())
+                        .map(|name| resolve(from, name))
+                        .transpose()?,
+                    decays_into: element
+                        .decays_into
+                        .as_ref()
+                        .map(|name| resolve(from, name))
+                        .transpose()?,
+                    crumbles_into: element
+                        .structure
+                        .as_ref()
+                        .and_then(|structure| structure.crumbles_into.as_ref())
+                        .map(|name| resolve(from, name))
+                        .transpose()?,
+                })
+            })
+            .collect::<Result<Arc<[Links]>, ElementError>>()?;
+
+        let mut reactions: Vec<Vec<ReactionRule>> =
+            definitions.elements.iter().map(|_| Vec::new()).collect();
+        for reaction in &definitions.reactions {
+            let from = reaction.reactants.join(" + ");
+            if !(0.0..=1.0).contains(&reaction.probability) {
+                return Err(out_of_range(&from, "probability", "between 0 and 1"));
+            }
+            let resolve_all = |names: &[String]| {
+                names
+                    .iter()
+                    .map(|name| resolve(&from, name))
+                    .collect::<Result<Vec<_>, _>>()
+            };
+            let rule = ReactionRule {
+                reactants: [
+                    resolve(&from, &reaction.reactants[0])?,
+                    resolve(&from, &reaction.reactants[1])?,
+                ],
+                products: [
+                    reaction.products[0]
+                        .as_ref()
+                        .map(|name| resolve(&from, name))
+                        .transpose()?,
+                    reaction.products[1]
+                        .as_ref()
+                        .map(|name| resolve(&from, name))
+                        .transpose()?,
+                ],
+                probability: reaction.probability,
+                catalysts: resolve_all(&reaction.catalysts)?,
+                byproducts: resolve_all(&reaction.byproducts)?,
+            };
+            reactions[rule.reactants[0].0 as usize].push(rule);
+        }
+
+        Ok(ElementRegistry {
+            elements: definitions.elements.iter().cloned().collect(),
+            links,
+            ids: Arc::new(ids),
+            reactions: reactions.into(),
+        })
+    }
+
+    /// ID of the element called `name`.
+    pub fn id(&self, name: &str) -> Result<ElementId, ElementError> {
+        self.ids
+            .get(name)
+            .copied()
+            .ok_or_else(|| ElementError::Unknown(name.to_string()))
+    }
+
+    /// Definition of the element with the given ID, which must come from this registry.
+    pub fn get(&self, id: ElementId) -> &Element {
+        &self.elements[id.0 as usize]
+    }
+
+    /// Every element, in ID order.
+    pub fn iter(&self) -> impl Iterator<Item = &Element> {
+        self.elements.iter()
+    }
+
+    /// The element `id` turns into at `temperature`, if any of its transitions applies.
+    pub fn transition_at(&self, id: ElementId, temperature: f32) -> Option<ElementId> {
+        self.links[id.0 as usize]
+            .transitions
+            .iter()
+            .find(|(threshold, _)| threshold.is_crossed_by(temperature))
+            .map(|&(_, into)| into)
+    }
+
+    pub fn burns_into(&self, id: ElementId) -> Option<ElementId> {
+        self.links[id.0 as usize].burns_into
+    }
+
+    pub fn emits_flame(&self, id: ElementId) -> Option<ElementId> {
+        self.links[id.0 as usize].emits_flame
+    }
+
+    pub fn emits_smoke(&self, id: ElementId) -> Option<ElementId> {
+        self.links[id.0 as usize].emits_smoke
+    }
+
+    pub fn decays_into(&self, id: ElementId) -> Option<ElementId> {
+        self.links[id.0 as usize].decays_into
+    }
+
+    pub fn crumbles_into(&self, id: ElementId) -> Option<ElementId> {
+        self.links[id.0 as usize].crumbles_into
+    }
+
+    /// Reactions whose first reactant is `id`.
+    pub fn reactions_of(&self, id: ElementId) -> &[ReactionRule] {
+        &self.reactions[id.0 as usize]
+    }
+}
+
+/// Checks the values of `element` that are used as chances, rates or ranges.
+fn check_ranges(element: &Element) -> Result<(), ElementError> {
+    let name = element.element.as_str();
+    if !(element.mass >= 0.0 && element.mass.is_finite()) {
+        return Err(out_of_range(name, "mass", "a number of at least 0"));
+    }
+    if !(0.0..=1.0).contains(&element.friction) {
+        return Err(out_of_range(name, "friction", "between 0 and 1"));
+    }
+    if !(0.0..=1.0).contains(&element.thermal_conductivity) {
+        return Err(out_of_range(
+            name,
+            "thermal_conductivity",
+            "between 0 and 1",
+        ));
+    }
+    if !(element.heat_capacity > 0.0 && element.heat_capacity.is_finite()) {
+        return Err(out_of_range(name, "heat_capacity", "a number above 0"));
+    }
+    // Gases use it as a percentage, liquids as a distance
+    let dispersion = element.dispersion_rate;
+    let (dispersion_ok, expected) = match element.element_type {
+        ElementType::Gas => ((0.0..=100.0).contains(&dispersion), "between 0 and 100"),
+        _ => (
+            dispersion >= 0.0 && dispersion.is_finite(),
+            "a number of at least 0",
+        ),
+    };
+    if !dispersion_ok {
+        return Err(out_of_range(name, "dispersion_rate", expected));
+    }
+    if let Some(combustion) = &element.combustion {
+        if !(0.0..=1.0).contains(&combustion.flammability) {
+            return Err(out_of_range(name, "flammability", "between 0 and 1"));
+        }
+    }
+    if let Some(lifetime) = &element.lifetime {
+        if lifetime.is_empty() {
+            return Err(out_of_range(
+                name,
+                "lifetime",
+                "a range that does not end before it starts",
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn out_of_range(from: &str, field: &'static str, expected: &'static str) -> ElementError {
+    ElementError::OutOfRange {
+        from: from.to_string(),
+        field,
+        expected,
+    }
+}
+
+impl Default for ElementRegistry {
+    fn default() -> Self {
+        ElementRegistry::new(&ElementDefinitions::default())
+            .expect("built-in element definitions are invalid")
+    }
+}
+
+#[derive(Debug)]
+pub enum ElementError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    Duplicate(String),
+    Empty,
+    TooMany,
+    Unknown(String),
+    /// An element or reaction names an element that is not defined.
+    UnknownReference {
+        from: String,
+        name: String,
+    },
+    /// A value of an element or reaction is outside the range it is used in.
+    OutOfRange {
+        from: String,
+        field: &'static str,
+        expected: &'static str,
+    },
+}
+
+impl fmt::Display for ElementError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ElementError::Io(error) => write!(f, "could not read element definitions: {error}"),
+            ElementError::Parse(error) => write!(f, "invalid element definitions: {error}"),
+            ElementError::Duplicate(name) => write!(f, "element \"{name}\" is defined twice"),
+            ElementError::Empty => write!(f, "no elements are defined"),
+            ElementError::TooMany => write!(
+                f,
+                "too many elements, at most {} fit",
+                u16::MAX as usize + 1
+            ),
+            ElementError::Unknown(name) => write!(f, "unknown element \"{name}\""),
+            ElementError::UnknownReference { from, name } => {
+                write!(f, "\"{from}\" refers to unknown element \"{name}\"")
+            }
+            ElementError::OutOfRange {
+                from,
+                field,
+                expected,
+            } => write!(f, "{field} of \"{from}\" must be {expected}"),
+        }
+    }
+}
+
+impl std::error::Error for ElementError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ElementError::Io(error) => Some(error),
+            ElementError::Parse(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ElementError {
+    fn from(error: io::Error) -> Self {
+        ElementError::Io(error)
+    }
+}
@@ -1,3 +1,5 @@
 pub mod element;
+pub mod element_registry;
 pub mod placement_shape;
-pub mod position;
+pub mod tick_counter;
+pub mod world_sprite;
@@ -1,7 +0,0 @@
-use bevy::prelude::*;
-
-#[derive(Component)]
-pub struct Position {
-    pub x: usize,
-    pub y: usize,
-}
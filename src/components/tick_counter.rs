@@ -0,0 +1,5 @@
+use bevy::prelude::*;
+
+/// Text showing the current simulation tick and speed.
+#[derive(Component)]
+pub struct TickCounter;
@@ -0,0 +1,6 @@
+use bevy::prelude::*;
+
+/// Sprite drawing part of the world itself, such as a wall or the particle grid. These are
+/// replaced whenever a new world is created.
+#[derive(Component)]
+pub struct WorldSprite;
@@ -0,0 +1,8 @@
+// Bevy systems take every resource and query they use as an argument
+#![allow(clippy::too_many_arguments)]
+
+pub mod components;
+pub mod resources;
+pub mod simulation;
+pub mod systems;
+pub mod utils;
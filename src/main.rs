@@ -1,36 +1,58 @@
 use bevy::prelude::*;
 use iyes_perf_ui::prelude::*;
 
-mod components;
-mod resources;
-mod systems;
-mod utils;
-
-use crate::resources::{MouseState, PlacementSize};
-use systems::*;
+use rust_sandbox::resources::{
+    DebugOverlay, FlowSettings, MouseState, PlacementSize, SimulationClock, SimulationSeed,
+    WorldBoundaries, WorldConfig,
+};
+use rust_sandbox::systems::{self, render::GridTexturePlugin, update::ElementDefinitionsPlugin, *};
+use rust_sandbox::utils;
 
 fn main() {
+    let world_config = WorldConfig::from_args().unwrap_or_else(|error| panic!("{error}"));
+    let seed = SimulationSeed::from_args().unwrap_or_else(|error| panic!("{error}"));
+    let world_boundaries = WorldBoundaries::from_args().unwrap_or_else(|error| panic!("{error}"));
     App::new()
         .add_plugins(DefaultPlugins)
         .add_plugins(bevy::diagnostic::FrameTimeDiagnosticsPlugin)
         .add_plugins(bevy::diagnostic::EntityCountDiagnosticsPlugin)
         .add_plugins(bevy::diagnostic::SystemInformationDiagnosticsPlugin)
         .add_plugins(PerfUiPlugin)
+        .add_plugins(GridTexturePlugin)
+        .add_plugins(ElementDefinitionsPlugin)
         .add_systems(Startup, (setup::camera, setup::world, setup::ui))
         .insert_resource(MouseState {
             button_pressed: false,
         })
         .insert_resource(PlacementSize::new())
+        .insert_resource(seed)
+        .insert_resource(world_boundaries)
+        .insert_resource(world_config)
+        .init_resource::<DebugOverlay>()
+        .init_resource::<SimulationClock>()
+        .init_resource::<FlowSettings>()
+        .add_systems(FixedUpdate, systems::update::particles)
         .add_systems(
             Update,
             (
                 utils::camera::edge_scrolling,
                 utils::camera::zoom_camera,
+                systems::update::reload_elements,
+                systems::update::new_world,
+                systems::update::stream_world
+                    .after(utils::camera::edge_scrolling)
+                    .before(systems::update::placement_shape),
                 systems::update::placement_shape,
                 systems::input::handle_input,
-                systems::update::particles,
+                systems::update::simulation_speed.after(systems::input::handle_input),
+                systems::render::grid_texture
+                    .after(systems::input::handle_input)
+                    .after(systems::update::new_world),
+                systems::render::chunk_overlay,
+                systems::render::tick_counter,
                 systems::update::mouse_state,
             ),
         )
+        .add_systems(Last, systems::update::save_world)
         .run();
 }
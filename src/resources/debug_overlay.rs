@@ -0,0 +1,10 @@
+use bevy::prelude::*;
+
+/// Debug visualisations that can be toggled at runtime.
+#[derive(Resource, Default)]
+pub struct DebugOverlay {
+    /// Outline awake simulation chunks and their dirty rectangles (F1).
+    pub chunks: bool,
+    /// Draw particles coloured by temperature instead of by element (F2).
+    pub heat: bool,
+}
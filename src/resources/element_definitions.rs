@@ -0,0 +1,10 @@
+use crate::components::element_registry::ElementDefinitions;
+use bevy::prelude::*;
+
+/// An element definitions file loaded as an asset.
+#[derive(Asset, TypePath, Clone)]
+pub struct ElementDefinitionsAsset(pub ElementDefinitions);
+
+/// Keeps the element definitions asset loaded so edits to the file are picked up.
+#[derive(Resource)]
+pub struct ElementDefinitionsHandle(pub Handle<ElementDefinitionsAsset>);
@@ -0,0 +1,7 @@
+use crate::components::element_registry::ElementRegistry;
+use bevy::prelude::*;
+
+/// The element definitions in use, shared with the simulation, for systems that look up
+/// elements by name or ID.
+#[derive(Resource, Clone, Deref)]
+pub struct Elements(pub ElementRegistry);
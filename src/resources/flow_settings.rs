@@ -0,0 +1,40 @@
+use crate::simulation::Flow;
+use bevy::prelude::*;
+
+/// Lowest and highest rate, in particles per tick, sources and sinks can be placed with.
+const MIN_RATE: f32 = 0.0625;
+const MAX_RATE: f32 = Flow::MAX_RATE;
+
+/// Settings given to sources and sinks placed with the brush. Sources create the last ordinary
+/// element selected, and sinks delete only that element, or anything once `\` is pressed.
+/// `;` and `'` halve and double the rate.
+#[derive(Resource)]
+pub struct FlowSettings {
+    /// Name of the element sources create and sinks delete, kept by name so it survives
+    /// definitions reloading.
+    pub element: String,
+    /// Whether sinks delete anything touching them rather than only `element`.
+    pub sinks_take_anything: bool,
+    /// Particles created or deleted per tick.
+    pub rate: f32,
+}
+
+impl Default for FlowSettings {
+    fn default() -> Self {
+        FlowSettings {
+            element: "Water".to_string(),
+            sinks_take_anything: false,
+            rate: 1.0,
+        }
+    }
+}
+
+impl FlowSettings {
+    pub fn faster(&mut self) {
+        self.rate = (self.rate * 2.0).min(MAX_RATE);
+    }
+
+    pub fn slower(&mut self) {
+        self.rate = (self.rate / 2.0).max(MIN_RATE);
+    }
+}
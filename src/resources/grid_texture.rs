@@ -0,0 +1,12 @@
+use bevy::prelude::*;
+use bevy::render::extract_resource::ExtractResource;
+
+/// Image the whole particle grid is drawn into.
+#[derive(Resource, Clone, ExtractResource)]
+pub struct GridTexture(pub Handle<Image>);
+
+/// Pixel rows to copy into the [`GridTexture`] this frame, as `(first texture row, rgba bytes)`.
+#[derive(Resource, Clone, Default, ExtractResource)]
+pub struct GridTextureUpload {
+    pub rows: Vec<(u32, Vec<u8>)>,
+}
@@ -1,10 +1,28 @@
+pub mod debug_overlay;
+pub mod element_definitions;
+pub mod elements;
+pub mod flow_settings;
+pub mod grid_texture;
 pub mod mouse_state;
 pub mod particle_matrix;
 pub mod placement_size;
 pub mod selected_element;
+pub mod simulation_clock;
+pub mod simulation_seed;
+pub mod world_boundaries;
+pub mod world_config;
 
+pub use debug_overlay::*;
+pub use element_definitions::*;
+pub use elements::*;
+pub use flow_settings::*;
+pub use grid_texture::*;
 pub use mouse_state::*;
 pub use particle_matrix::*;
 pub use placement_size::*;
 pub use selected_element::*;
+pub use simulation_clock::*;
+pub use simulation_seed::*;
+pub use world_boundaries::*;
+pub use world_config::*;
 
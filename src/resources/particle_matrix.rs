@@ -1,16 +1,84 @@
-use crate::utils::constants::{MATRIX_HEIGHT, MATRIX_WIDTH};
+use crate::components::element_registry::ElementRegistry;
+use crate::resources::world_config::WorldConfig;
+use crate::simulation::{ChunkStore, Simulation};
 use bevy::prelude::*;
+use std::io;
 
 // Resources
+/// Bevy-side wrapper around the headless [`Simulation`].
 #[derive(Resource)]
 pub struct ParticleMatrix {
-    pub matrix: Vec<Vec<Option<Entity>>>,
+    pub simulation: Simulation,
+    /// Chunks of an infinite world that are outside the simulated window, or `None` if the
+    /// world is bounded.
+    pub store: Option<ChunkStore>,
+    /// Whether the saved world, if there is one, has been loaded into the window. Until then
+    /// the window is neither saved nor moved, so nothing is written over what was saved.
+    loaded: bool,
 }
 
 impl ParticleMatrix {
-    pub fn new() -> Self {
+    /// An empty world of the configured size using `elements`. An infinite world saved to a
+    /// directory continues from what was saved there once [`ParticleMatrix::load`] is called,
+    /// which should only happen once the element definitions the world was saved with are in
+    /// use.
+    pub fn new(config: &WorldConfig, seed: u64, elements: ElementRegistry) -> Self {
+        let store = config.infinite.then(|| match &config.directory {
+            Some(directory) => ChunkStore::on_disk(directory.clone()),
+            None => ChunkStore::in_memory(),
+        });
+        let mut simulation = Simulation::new(config.width, config.height, seed);
+        simulation.set_elements(elements);
         ParticleMatrix {
-            matrix: vec![vec![None; MATRIX_WIDTH]; MATRIX_HEIGHT],
+            loaded: store
+                .as_ref()
+                .is_none_or(|store| store.directory().is_none()),
+            simulation,
+            store,
+        }
+    }
+
+    /// Whether the world streams in around the camera.
+    pub fn is_infinite(&self) -> bool {
+        self.store.is_some()
+    }
+
+    /// Whether the saved world has been loaded, or there is none to load.
+    pub fn is_loaded(&self) -> bool {
+        self.loaded
+    }
+
+    /// Moves the simulated window of an infinite world by whole chunks, see
+    /// [`Simulation::shift`]. Does nothing to a bounded world, or before the saved world has
+    /// been loaded.
+    pub fn shift(&mut self, dx: i64, dy: i64) -> io::Result<()> {
+        match &mut self.store {
+            Some(store) if self.loaded => self.simulation.shift(dx, dy, store),
+            _ => Ok(()),
+        }
+    }
+
+    /// Saves the simulated window of an infinite world to its directory, if it has one and the
+    /// saved world has been loaded.
+    pub fn save(&mut self) -> io::Result<()> {
+        match &mut self.store {
+            Some(store) if self.loaded && store.directory().is_some() => {
+                self.simulation.save_window(store)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Loads the simulated window of an infinite world from its directory, if it has one and
+    /// it has not been loaded yet. Elements are matched by name against the definitions in use.
+    pub fn load(&mut self) -> io::Result<()> {
+        if self.loaded {
+            return Ok(());
+        }
+        self.loaded = true;
+        match &mut self.store {
+            Some(store) => self.simulation.load_window(store),
+            None => Ok(()),
         }
     }
 }
@@ -14,3 +14,9 @@ impl PlacementSize {
         }
     }
 }
+
+impl Default for PlacementSize {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,5 +1,5 @@
-use crate::components::element::Element;
+use crate::components::element_registry::ElementId;
 use bevy::prelude::*;
 
 #[derive(Resource)]
-pub struct SelectedElement(pub Element);
+pub struct SelectedElement(pub ElementId);
@@ -0,0 +1,72 @@
+use bevy::prelude::*;
+
+/// Speeds the simulation can run at, as multiples of
+/// [`TICK_RATE`](crate::utils::constants::TICK_RATE).
+const SPEEDS: [f64; 6] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+
+/// Index of normal speed in [`SPEEDS`].
+const NORMAL_SPEED: usize = 2;
+
+/// Whether the simulation is running, and how fast. Space pauses and resumes, `.` advances a
+/// paused simulation by one tick, and `[` and `]` halve and double the speed.
+#[derive(Resource)]
+pub struct SimulationClock {
+    paused: bool,
+    /// Ticks requested while paused that have not run yet.
+    pending_steps: u32,
+    speed: usize,
+}
+
+impl Default for SimulationClock {
+    fn default() -> Self {
+        SimulationClock {
+            paused: false,
+            pending_steps: 0,
+            speed: NORMAL_SPEED,
+        }
+    }
+}
+
+impl SimulationClock {
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.pending_steps = 0;
+    }
+
+    /// Multiple of the normal tick rate the simulation runs at.
+    pub fn speed(&self) -> f64 {
+        SPEEDS[self.speed]
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed + 1).min(SPEEDS.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = self.speed.saturating_sub(1);
+    }
+
+    /// Runs one more tick while paused.
+    pub fn step(&mut self) {
+        if self.paused {
+            self.pending_steps += 1;
+        }
+    }
+
+    /// Whether the current fixed update should advance the simulation, using up a requested
+    /// step if paused.
+    pub fn tick(&mut self) -> bool {
+        if !self.paused {
+            return true;
+        }
+        if self.pending_steps == 0 {
+            return false;
+        }
+        self.pending_steps -= 1;
+        true
+    }
+}
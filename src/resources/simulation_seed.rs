@@ -0,0 +1,41 @@
+use bevy::prelude::*;
+use std::fmt;
+
+/// Seed for the simulation RNG. Passing the same seed with `--seed <u64>` replays a run exactly.
+#[derive(Resource, Clone, Copy)]
+pub struct SimulationSeed(pub u64);
+
+impl SimulationSeed {
+    /// Reads the seed from the `--seed` command line flag, falling back to a random seed if the
+    /// flag is not given. A value that is not a seed is an error rather than a random seed, so
+    /// a run is never mistaken for a replay.
+    pub fn from_args() -> Result<Self, InvalidSeed> {
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            if arg == "--seed" {
+                let value = args.next().unwrap_or_default();
+                return value
+                    .parse()
+                    .map(SimulationSeed)
+                    .map_err(|_| InvalidSeed(value));
+            }
+        }
+        Ok(SimulationSeed(rand::random()))
+    }
+}
+
+/// Error returned when `--seed` is not followed by a whole number that fits in a `u64`.
+#[derive(Debug)]
+pub struct InvalidSeed(pub String);
+
+impl fmt::Display for InvalidSeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid value \"{}\" for --seed, expected a whole number",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for InvalidSeed {}
@@ -0,0 +1,56 @@
+use crate::resources::world_config::WorldConfig;
+use crate::simulation::{Boundaries, Boundary, UnknownBoundary};
+use bevy::prelude::*;
+
+/// What happens to particles at each edge of the world. Set with `--left`, `--right`,
+/// `--bottom` and `--top`, or `--edges` for all four, each followed by `solid`, `wrap` or
+/// `open`; edges default to solid.
+///
+/// Wrapping is decided per axis: making one edge wrap makes the opposite edge wrap too, and
+/// across a wrapping edge the world simply carries on. Particles fall through a wrapping bottom
+/// edge and come down from the top, and nothing rests on it, so structures and rigid bodies are
+/// only held up by whatever is at the top of the world. Solid edges hold up whatever rests
+/// on them; open edges hold up nothing and delete what crosses them.
+#[derive(Resource, Clone, Copy, Default)]
+pub struct WorldBoundaries(pub Boundaries);
+
+impl WorldBoundaries {
+    /// Reads the boundaries from the command line flags, later flags overriding earlier ones.
+    /// A flag followed by anything other than a boundary name, or by nothing, is an error.
+    pub fn from_args() -> Result<Self, UnknownBoundary> {
+        let mut boundaries = Boundaries::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let edges: &mut [&mut Boundary] = match arg.as_str() {
+                "--left" => &mut [&mut boundaries.left],
+                "--right" => &mut [&mut boundaries.right],
+                "--bottom" => &mut [&mut boundaries.bottom],
+                "--top" => &mut [&mut boundaries.top],
+                "--edges" => &mut [
+                    &mut boundaries.left,
+                    &mut boundaries.right,
+                    &mut boundaries.bottom,
+                    &mut boundaries.top,
+                ],
+                _ => continue,
+            };
+            let boundary: Boundary = args.next().unwrap_or_default().parse()?;
+            for edge in edges {
+                **edge = boundary;
+            }
+        }
+        Ok(WorldBoundaries(boundaries.paired()))
+    }
+
+    /// Boundaries to give a world made with `config`. The edges of the window onto an infinite
+    /// world are always open, as the world carries on past them; particles that leave the
+    /// window there are lost rather than piling up against a wall that is not part of the
+    /// world.
+    pub fn of(&self, config: &WorldConfig) -> Boundaries {
+        if config.infinite {
+            Boundaries::all(Boundary::Open)
+        } else {
+            self.0
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use crate::simulation::CHUNK_CELLS;
+use crate::utils::constants::{
+    DEFAULT_CELL_SIZE, DEFAULT_WORLD_HEIGHT, DEFAULT_WORLD_WIDTH, MAX_WORLD_SIZE,
+};
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::{fmt, fs, io};
+
+/// Size of the world and of its cells on screen.
+///
+/// Read at startup, and again whenever a new world is created, from the RON file passed with
+/// `--config <path>`, then overridden by the `--width`, `--height` and `--cell-size` flags. Any
+/// value left out keeps its default. The world is centred on the origin.
+///
+/// With `--infinite` the world has no edges: the grid becomes a window onto an unbounded world
+/// that follows the camera, rounded up to whole chunks. `--world-dir <path>` saves the chunks
+/// that leave the window to that directory, so the world can be picked up again later.
+#[derive(Resource, Clone, PartialEq, Debug, Deserialize)]
+#[serde(default)]
+pub struct WorldConfig {
+    /// Width of the world in cells.
+    pub width: usize,
+    /// Height of the world in cells.
+    pub height: usize,
+    /// Side length of a cell, in pixels at the default zoom.
+    pub cell_size: f32,
+    /// Whether the world streams in around the camera instead of ending at its edges.
+    pub infinite: bool,
+    /// Where the chunks of an infinite world are saved, if anywhere.
+    pub directory: Option<PathBuf>,
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        WorldConfig {
+            width: DEFAULT_WORLD_WIDTH,
+            height: DEFAULT_WORLD_HEIGHT,
+            cell_size: DEFAULT_CELL_SIZE,
+            infinite: false,
+            directory: None,
+        }
+    }
+}
+
+impl WorldConfig {
+    /// Reads the configuration from the command line flags and the config file they name.
+    pub fn from_args() -> Result<Self, WorldConfigError> {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        let value_of = |flag: &str| {
+            args.iter()
+                .position(|arg| arg == flag)
+                .map(|index| {
+                    args.get(index + 1)
+                        .ok_or_else(|| WorldConfigError::MissingValue(flag.to_string()))
+                })
+                .transpose()
+        };
+
+        let mut config = match value_of("--config")? {
+            Some(path) => WorldConfig::load(PathBuf::from(path))?,
+            None => WorldConfig::default(),
+        };
+        if let Some(width) = value_of("--width")? {
+            config.width = parse_flag("--width", width)?;
+        }
+        if let Some(height) = value_of("--height")? {
+            config.height = parse_flag("--height", height)?;
+        }
+        if let Some(cell_size) = value_of("--cell-size")? {
+            config.cell_size = parse_flag("--cell-size", cell_size)?;
+        }
+        if args.iter().any(|arg| arg == "--infinite") {
+            config.infinite = true;
+        }
+        if let Some(directory) = value_of("--world-dir")? {
+            config.directory = Some(PathBuf::from(directory));
+        }
+        config.validate()
+    }
+
+    /// Reads a configuration written in RON, such as `(width: 200, height: 150)`.
+    pub fn load(path: PathBuf) -> Result<Self, WorldConfigError> {
+        let source =
+            fs::read_to_string(&path).map_err(|error| WorldConfigError::Io(path, error))?;
+        let config: WorldConfig = ron::from_str(&source).map_err(WorldConfigError::Parse)?;
+        config.validate()
+    }
+
+    fn validate(mut self) -> Result<Self, WorldConfigError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(WorldConfigError::Invalid(
+                "the world must be at least one cell wide and high",
+            ));
+        }
+        if !(self.cell_size > 0.0 && self.cell_size.is_finite()) {
+            return Err(WorldConfigError::Invalid(
+                "the cell size must be a positive number",
+            ));
+        }
+        if self.infinite {
+            // The window moves a whole chunk at a time
+            self.width = self.width.next_multiple_of(CHUNK_CELLS);
+            self.height = self.height.next_multiple_of(CHUNK_CELLS);
+        }
+        if self.width > MAX_WORLD_SIZE || self.height > MAX_WORLD_SIZE {
+            return Err(WorldConfigError::TooLarge {
+                width: self.width,
+                height: self.height,
+            });
+        }
+        Ok(self)
+    }
+
+    /// Side length of a chunk in pixels.
+    pub fn chunk_size(&self) -> f32 {
+        CHUNK_CELLS as f32 * self.cell_size
+    }
+
+    /// Size of the whole world, or of the window onto an infinite one, in pixels.
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.width as f32, self.height as f32) * self.cell_size
+    }
+
+    /// Bottom left corner of the world.
+    pub fn min(&self) -> Vec2 {
+        -self.size() / 2.0
+    }
+
+    /// Top right corner of the world.
+    pub fn max(&self) -> Vec2 {
+        self.size() / 2.0
+    }
+
+    /// Whether `position` lies inside the world.
+    pub fn contains(&self, position: Vec2) -> bool {
+        position.cmpge(self.min()).all() && position.cmple(self.max()).all()
+    }
+
+    /// Every cell overlapping the area from `min` to `max`, row by row from the bottom.
+    pub fn cells_in(&self, min: Vec2, max: Vec2) -> impl Iterator<Item = (usize, usize)> {
+        let first = ((min - self.min()) / self.cell_size)
+            .floor()
+            .max(Vec2::ZERO);
+        let last = ((max - self.min()) / self.cell_size)
+            .floor()
+            .min(Vec2::new(self.width as f32 - 1.0, self.height as f32 - 1.0));
+        let (columns, rows) = if first.cmple(last).all() {
+            (
+                first.x as usize..last.x as usize + 1,
+                first.y as usize..last.y as usize + 1,
+            )
+        } else {
+            (0..0, 0..0)
+        };
+        rows.flat_map(move |y| columns.clone().map(move |x| (x, y)))
+    }
+
+    /// Position of the bottom left corner of the cell at `(x, y)`.
+    pub fn cell_position(&self, x: usize, y: usize) -> Vec2 {
+        self.min() + Vec2::new(x as f32, y as f32) * self.cell_size
+    }
+}
+
+fn parse_flag<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, WorldConfigError> {
+    value.parse().map_err(|_| WorldConfigError::InvalidValue {
+        flag: flag.to_string(),
+        value: value.to_string(),
+    })
+}
+
+#[derive(Debug)]
+pub enum WorldConfigError {
+    Io(PathBuf, io::Error),
+    Parse(ron::error::SpannedError),
+    MissingValue(String),
+    InvalidValue {
+        flag: String,
+        value: String,
+    },
+    Invalid(&'static str),
+    /// The world is wider or higher than [`MAX_WORLD_SIZE`].
+    TooLarge {
+        width: usize,
+        height: usize,
+    },
+}
+
+impl fmt::Display for WorldConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WorldConfigError::Io(path, error) => {
+                write!(f, "could not read {}: {error}", path.display())
+            }
+            WorldConfigError::Parse(error) => write!(f, "invalid world configuration: {error}"),
+            WorldConfigError::MissingValue(flag) => write!(f, "{flag} needs a value"),
+            WorldConfigError::InvalidValue { flag, value } => {
+                write!(f, "invalid value \"{value}\" for {flag}")
+            }
+            WorldConfigError::Invalid(reason) => {
+                write!(f, "invalid world configuration: {reason}")
+            }
+            WorldConfigError::TooLarge { width, height } => write!(
+                f,
+                "invalid world configuration: {width}x{height} cells is larger than the \
+                 largest supported world, {MAX_WORLD_SIZE}x{MAX_WORLD_SIZE}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WorldConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WorldConfigError::Io(_, error) => Some(error),
+            WorldConfigError::Parse(error) => Some(error),
+            _ => None,
+        }
+    }
+}
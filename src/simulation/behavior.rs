@@ -0,0 +1,268 @@
+use crate::components::element::{Element, ElementType};
+use crate::components::element_registry::{ElementId, ElementRegistry};
+use crate::simulation::{
+    boundary::Location,
+    cell::{Cell, Velocity},
+    chunk::MAX_REACH,
+    grid::Grid,
+    rng::SimRng,
+};
+use crate::utils::particles::{Gas, Liquid, MovableSolid, Sink, Source};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// How the particles of an element move and change each tick.
+///
+/// `update` runs once per tick for every awake particle of the element, in parallel across
+/// chunks. It only describes what should happen through the [`ParticleContext`]; the requests
+/// are carried out once every particle of the chunk has been looked at.
+pub trait ParticleBehavior: Send + Sync {
+    fn update(&self, ctx: &mut ParticleContext);
+}
+
+/// Where a particle asked to go this tick.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Motion {
+    Stay,
+    /// Travel by an offset, which may cross a wrapping edge or leave through an open one.
+    MoveBy((isize, isize)),
+    SwapWith((usize, usize)),
+}
+
+/// A change to a cell other than moving it, applied before this tick's moves.
+pub(crate) enum Edit {
+    Spawn((usize, usize), ElementId),
+    Transform((usize, usize), ElementId),
+    Remove((usize, usize)),
+    /// Keeps the cell's chunk awake without changing it.
+    Wake((usize, usize)),
+}
+
+/// View of one particle and its surroundings handed to [`ParticleBehavior::update`].
+///
+/// Neighbours are addressed by their offset from the particle, with `dy` pointing up. Offsets
+/// may not exceed [`MAX_REACH`] in either direction, since that is as far as a chunk may safely
+/// read and write while other chunks are updated in parallel. Offsets past a wrapping edge of
+/// the grid continue on the opposite side.
+pub struct ParticleContext<'a> {
+    grid: &'a Grid,
+    elements: &'a ElementRegistry,
+    rng: &'a mut SimRng,
+    position: (usize, usize),
+    cell: &'a Cell,
+    velocity: Velocity,
+    direction: i8,
+    motion: Motion,
+    edits: &'a mut Vec<Edit>,
+}
+
+impl<'a> ParticleContext<'a> {
+    pub(crate) fn new(
+        grid: &'a Grid,
+        elements: &'a ElementRegistry,
+        rng: &'a mut SimRng,
+        position: (usize, usize),
+        cell: &'a Cell,
+        edits: &'a mut Vec<Edit>,
+    ) -> Self {
+        ParticleContext {
+            grid,
+            elements,
+            rng,
+            position,
+            cell,
+            velocity: cell.velocity,
+            direction: cell.direction,
+            motion: Motion::Stay,
+            edits,
+        }
+    }
+
+    /// The requested motion, and the velocity and direction the particle leaves with.
+    pub(crate) fn finish(self) -> (Motion, Velocity, i8) {
+        (self.motion, self.velocity, self.direction)
+    }
+
+    pub fn position(&self) -> (usize, usize) {
+        self.position
+    }
+
+    /// The particle being updated, as it was at the start of the tick.
+    pub fn cell(&self) -> &Cell {
+        self.cell
+    }
+
+    /// Definition of the particle's element.
+    pub fn element(&self) -> &Element {
+        self.elements.get(self.cell.element)
+    }
+
+    pub fn elements(&self) -> &ElementRegistry {
+        self.elements
+    }
+
+    pub fn rng(&mut self) -> &mut SimRng {
+        self.rng
+    }
+
+    /// The particle at offset `(dx, dy)`, or `None` if the cell is empty or past a solid or open
+    /// edge.
+    pub fn get(&self, dx: isize, dy: isize) -> Option<&Cell> {
+        self.locate(dx, dy).and_then(|(x, y)| self.grid.get(x, y))
+    }
+
+    /// Whether the particle could enter the cell at offset `(dx, dy)`: an empty cell, or the
+    /// void beyond an open edge.
+    pub fn is_empty(&self, dx: isize, dy: isize) -> bool {
+        check_reach(dx, dy);
+        match self
+            .grid
+            .locate(self.position.0 as isize + dx, self.position.1 as isize + dy)
+        {
+            Location::Inside((x, y)) => self.grid.is_empty(x, y),
+            Location::Wall => false,
+            Location::Void => true,
+        }
+    }
+
+    /// Furthest offset towards `(dx, dy)` the particle can travel before hitting a solid edge
+    /// or another particle. If the path leaves through an open edge, the offset lies beyond it.
+    pub fn trace(&self, dx: isize, dy: isize) -> (isize, isize) {
+        check_reach(dx, dy);
+        self.grid.trace(self.position, dx, dy)
+    }
+
+    /// Velocity the particle will leave with, starting out as its current one.
+    pub fn velocity(&self) -> Velocity {
+        self.velocity
+    }
+
+    pub fn set_velocity(&mut self, velocity: Velocity) {
+        self.velocity = velocity;
+    }
+
+    /// Sideways direction the particle will remember, starting out as its current one. See
+    /// [`Cell::direction`].
+    pub fn direction(&self) -> i8 {
+        self.direction
+    }
+
+    pub fn set_direction(&mut self, direction: i8) {
+        self.direction = direction;
+    }
+
+    /// Moves the particle towards offset `(dx, dy)`, stopping in front of the first obstacle.
+    /// A particle that leaves through an open edge is deleted. Replaces any earlier move or
+    /// swap requested this tick.
+    pub fn move_by(&mut self, dx: isize, dy: isize) {
+        self.motion = Motion::MoveBy(self.trace(dx, dy));
+    }
+
+    /// Trades places with whatever is at offset `(dx, dy)`, unless either cell was already
+    /// changed this tick by the time the swap is applied. Replaces any earlier move or swap.
+    pub fn swap_with(&mut self, dx: isize, dy: isize) {
+        if (dx, dy) == (0, 0) {
+            return;
+        }
+        if let Some(target) = self.locate(dx, dy) {
+            self.motion = Motion::SwapWith(target);
+        }
+    }
+
+    /// Places a fresh particle of `element` at offset `(dx, dy)` if that cell is still empty
+    /// when the request is applied.
+    pub fn spawn(&mut self, dx: isize, dy: isize, element: ElementId) {
+        if let Some(target) = self.locate(dx, dy) {
+            self.edits.push(Edit::Spawn(target, element));
+        }
+    }
+
+    /// Turns the particle at offset `(dx, dy)` into `element`, keeping its temperature and
+    /// velocity. `(0, 0)` transforms the particle itself.
+    pub fn transform(&mut self, dx: isize, dy: isize, element: ElementId) {
+        if let Some(target) = self.locate(dx, dy) {
+            self.edits.push(Edit::Transform(target, element));
+        }
+    }
+
+    /// Deletes the particle at offset `(dx, dy)`.
+    pub fn remove(&mut self, dx: isize, dy: isize) {
+        if let Some(target) = self.locate(dx, dy) {
+            self.edits.push(Edit::Remove(target));
+        }
+    }
+
+    /// Keeps the particle's chunk awake next tick even if nothing changes, for particles that
+    /// act on their own rather than in response to their surroundings.
+    pub fn stay_awake(&mut self) {
+        self.edits.push(Edit::Wake(self.position));
+    }
+
+    /// Grid position at offset `(dx, dy)`, or `None` if it is past a solid or open edge.
+    fn locate(&self, dx: isize, dy: isize) -> Option<(usize, usize)> {
+        check_reach(dx, dy);
+        self.grid.neighbour(self.position, dx, dy)
+    }
+}
+
+fn check_reach(dx: isize, dy: isize) {
+    assert!(
+        dx.unsigned_abs() <= MAX_REACH && dy.unsigned_abs() <= MAX_REACH,
+        "offset ({dx}, {dy}) is further than a particle can reach in one tick"
+    );
+}
+
+/// The behaviour of every element, resolved by ID. Elements get the built-in behaviour of their
+/// [`ElementType`] unless a custom one was registered under their name.
+#[derive(Clone, Default)]
+pub struct Behaviors {
+    custom: HashMap<String, Arc<dyn ParticleBehavior>>,
+    by_id: Vec<Option<Arc<dyn ParticleBehavior>>>,
+}
+
+impl Behaviors {
+    pub fn new(elements: &ElementRegistry) -> Self {
+        let mut behaviors = Behaviors::default();
+        behaviors.resolve(elements);
+        behaviors
+    }
+
+    /// Makes particles of the element called `name` follow `behavior`. The registration is kept
+    /// by name, so it survives element definitions being reloaded.
+    pub fn register(&mut self, name: &str, behavior: Arc<dyn ParticleBehavior>) {
+        self.custom.insert(name.to_string(), behavior);
+    }
+
+    /// Looks up the behaviour of every element of `elements` again, for when the element
+    /// definitions or the registrations changed.
+    pub fn resolve(&mut self, elements: &ElementRegistry) {
+        self.by_id = elements
+            .iter()
+            .map(|element| {
+                self.custom
+                    .get(&element.element)
+                    .cloned()
+                    .or_else(|| built_in(element.element_type))
+            })
+            .collect();
+    }
+
+    /// Behaviour of the element `id`, or `None` if its particles never move on their own.
+    pub fn get(&self, id: ElementId) -> Option<&dyn ParticleBehavior> {
+        self.by_id.get(id.0 as usize)?.as_deref()
+    }
+}
+
+fn built_in(element_type: ElementType) -> Option<Arc<dyn ParticleBehavior>> {
+    match element_type {
+        ElementType::MovableSolid => Some(Arc::new(MovableSolid)),
+        ElementType::Liquid => Some(Arc::new(Liquid)),
+        ElementType::Gas => Some(Arc::new(Gas)),
+        ElementType::Source => Some(Arc::new(Source)),
+        ElementType::Sink => Some(Arc::new(Sink)),
+        ElementType::ImmovableSolid
+        | ElementType::Erase
+        | ElementType::Heat
+        | ElementType::Cool => None,
+    }
+}
@@ -0,0 +1,188 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// What happens to particles at one edge of the grid.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Boundary {
+    /// An invisible wall that particles pile up against.
+    #[default]
+    Solid,
+    /// Particles leaving through the edge come back in on the opposite side. Wrapping applies
+    /// to a whole axis, see [`Boundaries::paired`].
+    Wrap,
+    /// Particles leaving through the edge are deleted.
+    Open,
+}
+
+impl FromStr for Boundary {
+    type Err = UnknownBoundary;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "solid" => Ok(Boundary::Solid),
+            "wrap" => Ok(Boundary::Wrap),
+            "open" => Ok(Boundary::Open),
+            _ => Err(UnknownBoundary(name.to_string())),
+        }
+    }
+}
+
+/// Error returned when parsing a [`Boundary`] from a name other than `solid`, `wrap` or `open`.
+#[derive(Debug)]
+pub struct UnknownBoundary(pub String);
+
+impl fmt::Display for UnknownBoundary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown boundary \"{}\", expected solid, wrap or open",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for UnknownBoundary {}
+
+/// The [`Boundary`] of each edge of the grid. All edges are solid by default.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Boundaries {
+    pub left: Boundary,
+    pub right: Boundary,
+    pub bottom: Boundary,
+    pub top: Boundary,
+}
+
+impl Boundaries {
+    /// The same boundary on every edge.
+    pub fn all(boundary: Boundary) -> Self {
+        Boundaries {
+            left: boundary,
+            right: boundary,
+            bottom: boundary,
+            top: boundary,
+        }
+    }
+
+    /// These boundaries with every wrapping edge paired with the opposite one, which the grid
+    /// applies them as. An axis either wraps at both ends or at neither, so particles cross it
+    /// in both directions and nothing rests on its edges: what lies past one is the other side
+    /// of the world, for movement and support alike.
+    pub fn paired(self) -> Self {
+        let pair = |a: Boundary, b: Boundary| {
+            if a == Boundary::Wrap || b == Boundary::Wrap {
+                (Boundary::Wrap, Boundary::Wrap)
+            } else {
+                (a, b)
+            }
+        };
+        let (left, right) = pair(self.left, self.right);
+        let (bottom, top) = pair(self.bottom, self.top);
+        Boundaries {
+            left,
+            right,
+            bottom,
+            top,
+        }
+    }
+
+    /// Whether particles can cross from the left edge to the right one or back.
+    pub fn wraps_horizontally(&self) -> bool {
+        self.left == Boundary::Wrap || self.right == Boundary::Wrap
+    }
+
+    /// Whether particles can cross from the bottom edge to the top one or back.
+    pub fn wraps_vertically(&self) -> bool {
+        self.bottom == Boundary::Wrap || self.top == Boundary::Wrap
+    }
+}
+
+/// What lies at a position that may be beyond the edges of the grid.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Location {
+    /// A cell of the grid, after wrapping around any wrapping edge that was crossed.
+    Inside((usize, usize)),
+    /// Past a solid edge.
+    Wall,
+    /// Past an open edge, where particles are deleted.
+    Void,
+}
+
+/// Resolves coordinate `v` along an axis of `len` cells, whose low and high ends have the given
+/// boundaries.
+pub(crate) fn resolve_axis(
+    v: isize,
+    len: usize,
+    low: Boundary,
+    high: Boundary,
+) -> Result<usize, Location> {
+    if v >= 0 && (v as usize) < len {
+        return Ok(v as usize);
+    }
+    match if v < 0 { low } else { high } {
+        Boundary::Solid => Err(Location::Wall),
+        Boundary::Open => Err(Location::Void),
+        Boundary::Wrap => Ok(v.rem_euclid(len as isize) as usize),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{Cell, Grid, Simulation};
+
+    #[test]
+    fn wrapping_one_edge_wraps_its_axis() {
+        let boundaries = Boundaries {
+            bottom: Boundary::Wrap,
+            left: Boundary::Open,
+            ..Boundaries::default()
+        }
+        .paired();
+        assert_eq!(boundaries.top, Boundary::Wrap);
+        assert_eq!(boundaries.left, Boundary::Open);
+        assert_eq!(boundaries.right, Boundary::Solid);
+
+        let mut grid = Grid::new(8, 8);
+        grid.set_boundaries(Boundaries {
+            bottom: Boundary::Wrap,
+            ..Boundaries::default()
+        });
+        assert_eq!(grid.locate(3, -1), Location::Inside((3, 7)));
+        assert_eq!(grid.locate(3, 8), Location::Inside((3, 0)));
+        assert_eq!(grid.locate(-1, 3), Location::Wall);
+    }
+
+    #[test]
+    fn particles_fall_through_a_wrapping_bottom_edge() {
+        let mut simulation = Simulation::new(32, 64, 1);
+        simulation.set_boundaries(Boundaries {
+            bottom: Boundary::Wrap,
+            ..Boundaries::default()
+        });
+        let sand = simulation.elements().id("Sand").unwrap();
+        let cell = Cell::new(simulation.elements(), sand, [255; 4]);
+        simulation.set_cell(16, 0, Some(cell));
+        simulation.step();
+
+        assert!(simulation.get_cell(16, 0).is_none());
+        assert!((32..64).any(|y| simulation.get_cell(16, y).is_some()));
+    }
+
+    #[test]
+    fn nothing_rests_on_a_wrapping_bottom_edge() {
+        let mut simulation = Simulation::new(32, 64, 1);
+        simulation.set_boundaries(Boundaries {
+            bottom: Boundary::Wrap,
+            ..Boundaries::default()
+        });
+        let stone = simulation.elements().id("Stone").unwrap();
+        for x in 10..14 {
+            let cell = Cell::new(simulation.elements(), stone, [255; 4]);
+            simulation.set_cell(x, 0, Some(cell));
+        }
+        for _ in 0..5 {
+            simulation.step();
+        }
+        assert!((10..14).all(|x| simulation.get_cell(x, 0).is_none()));
+    }
+}
@@ -0,0 +1,220 @@
+use crate::components::element::{Element, ElementType};
+use crate::components::element_registry::{ElementId, ElementRegistry};
+use crate::simulation::{chunk::MAX_REACH, rigid::BodyId};
+use rand::Rng;
+
+/// Temperature, in degrees Celsius, that newly placed particles start at.
+pub const AMBIENT_TEMPERATURE: f32 = 20.0;
+
+/// Lowest temperature a particle can be cooled to.
+pub const ABSOLUTE_ZERO: f32 = -273.15;
+
+/// Downward acceleration of falling particles, in cells per tick per tick.
+pub const GRAVITY: f32 = 0.3;
+
+/// Top speed of a particle in cells per tick, bounded by how far a chunk may reach in one tick.
+pub const MAX_SPEED: f32 = MAX_REACH as f32;
+
+/// Share of the fall speed turned into sideways motion when a particle hits the ground.
+pub const SCATTER: f32 = 0.3;
+
+/// Velocity of a particle in cells per tick, with `y` pointing up.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct Velocity {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Velocity {
+    /// Applies one tick of gravity. A falling particle always moves at least one cell.
+    pub fn accelerate(&mut self) {
+        self.y = (self.y - GRAVITY).clamp(-MAX_SPEED, -1.0);
+        self.x = self.x.clamp(-MAX_SPEED, MAX_SPEED);
+    }
+
+    /// Turns the remaining fall speed into a sideways scatter in a random direction, damped by
+    /// `friction`.
+    pub fn land(&mut self, rng: &mut impl Rng, friction: f32) {
+        if self.y < 0.0 {
+            let direction = if rng.gen_bool(0.5) { -1.0 } else { 1.0 };
+            let scatter = -self.y * SCATTER * (1.0 - friction.clamp(0.0, 1.0));
+            self.x = (self.x + direction * scatter).clamp(-MAX_SPEED, MAX_SPEED);
+            self.y = 0.0;
+        }
+    }
+
+    /// Slows horizontal motion by `friction`, stopping it entirely below one cell per tick.
+    pub fn brake(&mut self, friction: f32) {
+        self.x *= 1.0 - friction.clamp(0.0, 1.0);
+        if self.x.abs() < 1.0 {
+            self.x = 0.0;
+        }
+    }
+
+    /// Whole cells travelled this tick.
+    pub fn offset(&self) -> (isize, isize) {
+        (self.x.round() as isize, self.y.round() as isize)
+    }
+}
+
+/// Remaining and total lifetime of a particle that decays, in ticks.
+#[derive(Clone, Copy)]
+pub struct Lifetime {
+    pub left: u32,
+    pub total: u32,
+}
+
+impl Lifetime {
+    /// Opacity of the particle, fading from 1 to 0 as its life runs out.
+    pub fn alpha(&self) -> f32 {
+        self.left as f32 / self.total.max(1) as f32
+    }
+}
+
+/// Settings of a [`Source`](ElementType::Source) or [`Sink`](ElementType::Sink) particle.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Flow {
+    /// Element a source creates, or the only element a sink deletes. A source without one
+    /// creates nothing, and a sink without one deletes anything but other sources and sinks.
+    pub element: Option<ElementId>,
+    /// Particles created or deleted per tick, with any fraction being the chance of one more.
+    pub rate: f32,
+}
+
+impl Default for Flow {
+    fn default() -> Self {
+        Flow {
+            element: None,
+            rate: 1.0,
+        }
+    }
+}
+
+impl Flow {
+    /// Most particles a source or sink can create or delete per tick.
+    pub const MAX_RATE: f32 = 8.0;
+
+    /// Default settings for a particle of `element`, or `None` if it is not a source or sink.
+    pub fn of(element: &Element) -> Option<Flow> {
+        matches!(
+            element.element_type,
+            ElementType::Source | ElementType::Sink
+        )
+        .then(Flow::default)
+    }
+
+    /// These settings with the rate kept within `0..=MAX_RATE`, a rate that is not a number
+    /// counting as zero.
+    pub fn clamped(self) -> Flow {
+        let rate = if self.rate.is_nan() {
+            0.0
+        } else {
+            self.rate.clamp(0.0, Flow::MAX_RATE)
+        };
+        Flow { rate, ..self }
+    }
+
+    /// Number of particles to create or delete this tick.
+    pub fn roll(&self, rng: &mut impl Rng) -> usize {
+        let rate = self.rate.max(0.0);
+        let extra = rng.gen_bool(rate.fract() as f64);
+        rate as usize + extra as usize
+    }
+}
+
+/// A single occupied cell of the simulation grid, holding all per-particle state.
+#[derive(Clone)]
+pub struct Cell {
+    /// What the particle is made of; its properties are looked up in the [`ElementRegistry`].
+    pub element: ElementId,
+    /// sRGB colour of this particle, picked once when it is placed.
+    pub color: [u8; 4],
+    pub velocity: Velocity,
+    /// Sideways direction the particle last spread in: `-1` left, `1` right, `0` not yet.
+    pub direction: i8,
+    pub temperature: f32,
+    /// Pressure from the last pressure pass: for liquids the depth in cells below the highest
+    /// surface of their body, for gases the share of their chamber filled with gas.
+    pub pressure: f32,
+    /// For solids with a [`Structure`](crate::components::element::Structure), how far the
+    /// particle is from support as of the last structure pass.
+    pub stress: u16,
+    /// Ticks left before the particle decays, or `None` if it lives forever.
+    pub lifetime: Option<Lifetime>,
+    /// Ticks left before a burning particle is used up, or `None` if it is not on fire.
+    pub burning: Option<u32>,
+    /// Rigid body the particle is part of while that body is in motion.
+    pub body: Option<BodyId>,
+    /// What a source or sink creates or deletes, `None` for every other particle.
+    pub flow: Option<Flow>,
+    /// Last tick in which this particle moved, so it is not simulated twice in one tick.
+    pub last_update: u64,
+}
+
+impl Cell {
+    /// A particle of `id` at the element's starting temperature, already burning if that is
+    /// above its ignition temperature.
+    pub fn new(elements: &ElementRegistry, id: ElementId, color: [u8; 4]) -> Self {
+        let element = elements.get(id);
+        let burning = element
+            .combustion
+            .as_ref()
+            .filter(|combustion| element.temperature >= combustion.ignition_temperature)
+            .map(|combustion| combustion.burn_duration);
+        Cell {
+            element: id,
+            color,
+            velocity: Velocity::default(),
+            direction: 0,
+            temperature: element.temperature,
+            pressure: 0.0,
+            stress: 0,
+            lifetime: None,
+            burning,
+            body: None,
+            flow: Flow::of(element),
+            last_update: 0,
+        }
+    }
+
+    /// A fresh particle of `id`, with the usual colour variation for its type and a lifetime
+    /// picked from the element's range.
+    pub fn spawn(elements: &ElementRegistry, id: ElementId, rng: &mut impl Rng) -> Self {
+        let element = elements.get(id);
+        let color = particle_color(element, rng);
+        let lifetime = roll_lifetime(element, rng);
+        Cell {
+            lifetime,
+            ..Cell::new(elements, id, color)
+        }
+    }
+
+    /// Turns this particle into `id` in place, keeping its temperature and velocity. Any fire
+    /// goes out, the particle breaks off any rigid body it was part of, and any source or sink
+    /// settings are reset.
+    pub fn transform(&mut self, elements: &ElementRegistry, id: ElementId, rng: &mut impl Rng) {
+        let element = elements.get(id);
+        self.color = particle_color(element, rng);
+        self.burning = None;
+        self.body = None;
+        self.flow = Flow::of(element);
+        self.lifetime = roll_lifetime(element, rng);
+        self.element = id;
+    }
+}
+
+fn roll_lifetime(element: &Element, rng: &mut impl Rng) -> Option<Lifetime> {
+    element.lifetime.clone().map(|range| {
+        let total = rng.gen_range(range);
+        Lifetime { left: total, total }
+    })
+}
+
+fn particle_color(element: &Element, rng: &mut impl Rng) -> [u8; 4] {
+    match element.element_type {
+        // Decaying particles start opaque and fade out with their lifetime
+        _ if element.lifetime.is_some() => element.color_bytes(1.0),
+        ElementType::Liquid => element.color_bytes(0.1),
+        _ => element.color_bytes(rng.gen_range(0.5..=1.0)),
+    }
+}
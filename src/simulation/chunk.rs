@@ -0,0 +1,232 @@
+/// Side length, in cells, of the square chunks the grid is split into for sleep tracking.
+pub const CHUNK_CELLS: usize = 32;
+
+/// Furthest a particle may read or move away from its chunk in a single tick. Keeping it under
+/// half a chunk means chunks two apart never touch the same cells, which is what allows the
+/// checkerboard phases to run in parallel.
+pub const MAX_REACH: usize = CHUNK_CELLS / 2 - 1;
+
+use std::ops::Range;
+
+/// Rectangle of cells in grid coordinates. `max_x` and `max_y` are exclusive.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Rect {
+    pub min_x: usize,
+    pub min_y: usize,
+    pub max_x: usize,
+    pub max_y: usize,
+}
+
+impl Rect {
+    pub fn union(self, other: Rect) -> Rect {
+        Rect {
+            min_x: self.min_x.min(other.min_x),
+            min_y: self.min_y.min(other.min_y),
+            max_x: self.max_x.max(other.max_x),
+            max_y: self.max_y.max(other.max_y),
+        }
+    }
+
+    pub fn intersect(self, other: Rect) -> Rect {
+        Rect {
+            min_x: self.min_x.max(other.min_x),
+            min_y: self.min_y.max(other.min_y),
+            max_x: self.max_x.min(other.max_x),
+            max_y: self.max_y.min(other.max_y),
+        }
+    }
+}
+
+/// Sleep state of one chunk. Only the dirty rectangle of an awake chunk is simulated.
+#[derive(Clone, Default)]
+pub struct Chunk {
+    /// Cells simulated during the current tick, or `None` if the chunk is asleep.
+    pub dirty: Option<Rect>,
+    /// Cells woken during the current tick, to be simulated on the next one.
+    next_dirty: Option<Rect>,
+    /// Whether temperatures in this chunk are still settling, so heat keeps being exchanged
+    /// even when nothing moves.
+    pub warm: bool,
+    next_warm: bool,
+}
+
+/// The grid split into [`CHUNK_CELLS`] sized chunks, each tracking which of its cells need
+/// simulating.
+pub struct Chunks {
+    width: usize,
+    height: usize,
+    columns: usize,
+    rows: usize,
+    chunks: Vec<Chunk>,
+    /// Whether the grid wraps around horizontally and vertically.
+    wrap: (bool, bool),
+}
+
+impl Chunks {
+    pub fn new(width: usize, height: usize) -> Self {
+        let columns = width.div_ceil(CHUNK_CELLS);
+        let rows = height.div_ceil(CHUNK_CELLS);
+        Chunks {
+            width,
+            height,
+            columns,
+            rows,
+            chunks: vec![Chunk::default(); columns * rows],
+            wrap: (false, false),
+        }
+    }
+
+    /// Sets whether particles can cross between opposite edges, horizontally and vertically.
+    /// Chunks near such an edge are then updated on their own, see [`Chunks::seams`].
+    pub fn set_wrap(&mut self, horizontal: bool, vertical: bool) {
+        self.wrap = (horizontal, vertical);
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn get(&self, column: usize, row: usize) -> &Chunk {
+        &self.chunks[row * self.columns + column]
+    }
+
+    /// Cells covered by the chunk at `(column, row)`. Chunks on the right and top edges may be
+    /// smaller than [`CHUNK_CELLS`].
+    pub fn bounds(&self, column: usize, row: usize) -> Rect {
+        Rect {
+            min_x: column * CHUNK_CELLS,
+            min_y: row * CHUNK_CELLS,
+            max_x: ((column + 1) * CHUNK_CELLS).min(self.width),
+            max_y: ((row + 1) * CHUNK_CELLS).min(self.height),
+        }
+    }
+
+    /// Wakes the cell at `(x, y)` and its direct neighbours, including any that lie in a
+    /// neighbouring chunk or across a wrapping edge.
+    pub fn wake(&mut self, x: usize, y: usize) {
+        for columns in around(x, self.width, self.wrap.0) {
+            for rows in around(y, self.height, self.wrap.1) {
+                self.wake_area(Rect {
+                    min_x: columns.start,
+                    min_y: rows.start,
+                    max_x: columns.end,
+                    max_y: rows.end,
+                });
+            }
+        }
+    }
+
+    /// Wakes every chunk, so the whole grid is simulated on the next tick.
+    pub fn wake_all(&mut self) {
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let bounds = self.bounds(column, row);
+                self.chunks[row * self.columns + column].next_dirty = Some(bounds);
+            }
+        }
+    }
+
+    fn wake_area(&mut self, area: Rect) {
+        for row in area.min_y / CHUNK_CELLS..=(area.max_y - 1) / CHUNK_CELLS {
+            for column in area.min_x / CHUNK_CELLS..=(area.max_x - 1) / CHUNK_CELLS {
+                let woken = area.intersect(self.bounds(column, row));
+                let chunk = &mut self.chunks[row * self.columns + column];
+                chunk.next_dirty = Some(match chunk.next_dirty {
+                    Some(dirty) => dirty.union(woken),
+                    None => woken,
+                });
+            }
+        }
+    }
+
+    /// Starts a new tick: everything woken during the previous tick becomes the area to simulate
+    /// and every other chunk goes to sleep.
+    pub fn advance(&mut self) {
+        for chunk in &mut self.chunks {
+            chunk.dirty = chunk.next_dirty.take();
+            chunk.warm = std::mem::take(&mut chunk.next_warm);
+        }
+    }
+
+    /// Keeps the chunk at `index` and its neighbours exchanging heat during the next tick.
+    pub fn keep_warm(&mut self, index: usize) {
+        let (column, row) = (index % self.columns, index / self.columns);
+        for row in row.saturating_sub(1)..(row + 2).min(self.rows) {
+            for column in column.saturating_sub(1)..(column + 2).min(self.columns) {
+                self.chunks[row * self.columns + column].next_warm = true;
+            }
+        }
+    }
+
+    /// Chunks that need heat exchanged this tick, as `(chunk index, chunk bounds)`: every chunk
+    /// that is awake or still warm.
+    pub fn thermal(&self) -> Vec<(usize, Rect)> {
+        (0..self.chunks.len())
+            .filter(|&index| self.chunks[index].dirty.is_some() || self.chunks[index].warm)
+            .map(|index| {
+                (
+                    index,
+                    self.bounds(index % self.columns, index / self.columns),
+                )
+            })
+            .collect()
+    }
+
+    /// Every awake chunk, as `(chunk index, dirty rectangle)`.
+    pub fn awake(&self) -> Vec<(usize, Rect)> {
+        self.chunks
+            .iter()
+            .enumerate()
+            .filter_map(|(index, chunk)| chunk.dirty.map(|dirty| (index, dirty)))
+            .collect()
+    }
+
+    /// Awake chunks of one checkerboard phase, as `(chunk index, dirty rectangle)`. Phase `0`
+    /// holds chunks with even column and even row, `1` odd column and even row, `2` even column
+    /// and odd row and `3` odd column and odd row. Seam chunks are left out.
+    pub fn phase(&self, phase: usize) -> Vec<(usize, Rect)> {
+        let (column_parity, row_parity) = (phase % 2, phase / 2);
+        (row_parity..self.rows)
+            .step_by(2)
+            .flat_map(|row| {
+                (column_parity..self.columns)
+                    .step_by(2)
+                    .map(move |column| row * self.columns + column)
+            })
+            .filter(|&index| !self.is_seam(index))
+            .filter_map(|index| self.chunks[index].dirty.map(|dirty| (index, dirty)))
+            .collect()
+    }
+
+    /// Awake chunks close enough to a wrapping edge to reach across it, as `(chunk index, dirty
+    /// rectangle)`. What they reach on the other side may belong to a chunk of the same phase,
+    /// so these must be updated one at a time instead.
+    pub fn seams(&self) -> Vec<(usize, Rect)> {
+        (0..self.chunks.len())
+            .filter(|&index| self.is_seam(index))
+            .filter_map(|index| self.chunks[index].dirty.map(|dirty| (index, dirty)))
+            .collect()
+    }
+
+    fn is_seam(&self, index: usize) -> bool {
+        let bounds = self.bounds(index % self.columns, index / self.columns);
+        let near = |min: usize, max: usize, len: usize| min < MAX_REACH || max + MAX_REACH > len;
+        (self.wrap.0 && near(bounds.min_x, bounds.max_x, self.width))
+            || (self.wrap.1 && near(bounds.min_y, bounds.max_y, self.height))
+    }
+}
+
+/// Cells within one of `v` along an axis of `len` cells: the range around it, plus the cell
+/// on the opposite edge if the axis wraps and `v` is at an edge.
+fn around(v: usize, len: usize, wrap: bool) -> impl Iterator<Item = Range<usize>> {
+    let opposite = match v {
+        0 if wrap => Some(len - 1..len),
+        _ if wrap && v + 1 == len => Some(0..1),
+        _ => None,
+    };
+    std::iter::once(v.saturating_sub(1)..(v + 2).min(len)).chain(opposite)
+}
@@ -0,0 +1,44 @@
+use crate::components::element_registry::ElementRegistry;
+use crate::simulation::{cell::Cell, chunk::Rect, grid::Grid, rng::SimRng};
+
+/// Ages the decaying particles in `rect` by one tick, fading them out and replacing those whose
+/// lifetime ran out with their decay product. Returns the cells that changed.
+///
+/// # Safety
+///
+/// Writes the cells of `rect` through a shared reference, so the caller must guarantee that no
+/// other thread touches them during the call, as for [`Grid::move_cell_shared`].
+pub(crate) unsafe fn decay(
+    grid: &Grid,
+    elements: &ElementRegistry,
+    rect: Rect,
+    rng: &mut SimRng,
+) -> Vec<(usize, usize)> {
+    let mut changed = Vec::new();
+
+    for y in rect.min_y..rect.max_y {
+        for x in rect.min_x..rect.max_x {
+            let decayed = grid.with_cell_shared(x, y, |slot| {
+                let cell = slot.as_mut()?;
+                let lifetime = cell.lifetime.as_mut()?;
+                lifetime.left = lifetime.left.saturating_sub(1);
+                cell.color[3] = (lifetime.alpha() * 255.0).round() as u8;
+                Some(lifetime.left == 0)
+            });
+            match decayed {
+                Some(true) => {
+                    grid.with_cell_shared(x, y, |slot| {
+                        let product = slot
+                            .take()
+                            .and_then(|cell| elements.decays_into(cell.element));
+                        *slot = product.map(|product| Cell::spawn(elements, product, rng));
+                    });
+                    changed.push((x, y));
+                }
+                Some(false) => changed.push((x, y)),
+                None => {}
+            }
+        }
+    }
+    changed
+}
@@ -0,0 +1,872 @@
+use crate::components::element::{Element, ElementType};
+use crate::components::element_registry::{ElementId, ElementRegistry, ReactionRule};
+use crate::simulation::{
+    behavior::{Behaviors, Edit, Motion, ParticleBehavior, ParticleContext},
+    boundary::{Boundaries, Location},
+    cell::{Cell, Flow, Velocity, ABSOLUTE_ZERO},
+    chunk::{Rect, CHUNK_CELLS},
+    decay, fire,
+    grid::Grid,
+    heat, pressure,
+    rigid::RigidBodies,
+    rng::{chunk_rng, seeded_rng, SimRng},
+    stream::{ChunkCoord, ChunkStore},
+    structure,
+};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::io;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Headless falling-sand simulation. Owns all cell data and knows nothing about rendering.
+///
+/// Every random decision is drawn from RNGs derived from a single seed, so the same seed and the
+/// same sequence of `set_cell` and `step` calls always produce the same world, whatever the
+/// number of threads.
+pub struct Simulation {
+    grid: Grid,
+    seed: u64,
+    rng: SimRng,
+    tick: u64,
+    thread_pool: ThreadPool,
+    elements: ElementRegistry,
+    behaviors: Behaviors,
+    bodies: RigidBodies,
+    /// Scratch space of the pressure pass, reused every tick.
+    pressure: pressure::Visited,
+    /// Chunk of the unbounded world shown in the bottom left corner of the grid.
+    origin: ChunkCoord,
+}
+
+impl Simulation {
+    pub fn new(width: usize, height: usize, seed: u64) -> Self {
+        Simulation {
+            grid: Grid::new(width, height),
+            seed,
+            rng: seeded_rng(seed),
+            tick: 0,
+            thread_pool: build_thread_pool(0),
+            behaviors: Behaviors::new(&ElementRegistry::default()),
+            elements: ElementRegistry::default(),
+            bodies: RigidBodies::default(),
+            pressure: pressure::Visited::default(),
+            origin: (0, 0),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Number of ticks simulated so far.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Sets how many threads update chunks, `0` meaning one per CPU. The result of a step is the
+    /// same for any thread count.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.thread_pool = build_thread_pool(threads);
+    }
+
+    /// Elements and reactions the simulation currently uses.
+    pub fn elements(&self) -> &ElementRegistry {
+        &self.elements
+    }
+
+    /// Replaces the element definitions and reactions. Particles already in the world are
+    /// matched to the new definitions by name and take on their colour; particles whose element
+    /// is no longer defined are removed, and sources and sinks set to such an element are
+    /// cleared.
+    pub fn set_elements(&mut self, elements: ElementRegistry) {
+        for y in 0..self.grid.height() {
+            for x in 0..self.grid.width() {
+                let Some(cell) = self.grid.get(x, y) else {
+                    continue;
+                };
+                let Ok(id) = elements.id(&self.elements.get(cell.element).element) else {
+                    self.grid.set(x, y, None);
+                    continue;
+                };
+                // Sources and sinks keep their settings, their element also matched by name
+                let flow = cell.flow.map(|flow| Flow {
+                    element: flow
+                        .element
+                        .and_then(|element| elements.id(&self.elements.get(element).element).ok()),
+                    ..flow
+                });
+                if let Some(cell) = self.grid.get_mut(x, y) {
+                    let alpha = cell.color[3];
+                    cell.color = elements.get(id).color_bytes(1.0);
+                    cell.color[3] = alpha;
+                    cell.element = id;
+                    cell.flow = Flow::of(elements.get(id)).map(|default| flow.unwrap_or(default));
+                }
+            }
+        }
+        self.behaviors.resolve(&elements);
+        self.elements = elements;
+        self.grid.redraw_all();
+    }
+
+    /// Makes particles of the element called `name` follow `behavior` instead of the built-in
+    /// behaviour of their [`ElementType`]. The element does not need to be defined yet; the
+    /// behaviour applies whenever definitions with that name are in use.
+    pub fn set_behavior(&mut self, name: &str, behavior: impl ParticleBehavior + 'static) {
+        self.behaviors.register(name, Arc::new(behavior));
+        self.behaviors.resolve(&self.elements);
+    }
+
+    /// What happens to particles at each edge of the grid.
+    pub fn boundaries(&self) -> Boundaries {
+        self.grid.boundaries()
+    }
+
+    pub fn set_boundaries(&mut self, boundaries: Boundaries) {
+        self.grid.set_boundaries(boundaries);
+    }
+
+    /// Rigid bodies currently falling or turning.
+    pub fn rigid_bodies(&self) -> &RigidBodies {
+        &self.bodies
+    }
+
+    /// The simulation RNG, for callers that need randomness that must stay reproducible (such as
+    /// picking the colour of a newly placed particle).
+    pub fn rng(&mut self) -> &mut SimRng {
+        &mut self.rng
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    pub fn get_cell(&self, x: usize, y: usize) -> Option<&Cell> {
+        self.grid.get(x, y)
+    }
+
+    pub fn set_cell(&mut self, x: usize, y: usize, cell: Option<Cell>) -> Option<Cell> {
+        self.grid.set(x, y, cell)
+    }
+
+    /// Places a fresh particle of `element` at `(x, y)`, replacing whatever was there.
+    pub fn spawn(&mut self, x: usize, y: usize, element: ElementId) {
+        let cell = Cell::spawn(&self.elements, element, &mut self.rng);
+        self.grid.set(x, y, Some(cell));
+    }
+
+    /// Changes what the source or sink at `(x, y)` creates or deletes, see [`Flow::clamped`].
+    /// Does nothing if there is no source or sink there.
+    pub fn set_flow(&mut self, x: usize, y: usize, flow: Flow) {
+        if let Some(cell) = self.grid.get_mut(x, y) {
+            if cell.flow.is_some() {
+                cell.flow = Some(flow.clamped());
+                self.grid.mark_changed(x, y);
+            }
+        }
+    }
+
+    /// Adds `delta` degrees to the particle at `(x, y)`, if there is one, and wakes it so the
+    /// heat spreads and any phase transition happens.
+    pub fn add_heat(&mut self, x: usize, y: usize, delta: f32) {
+        if let Some(cell) = self.grid.get(x, y) {
+            let temperature = (cell.temperature + delta).max(ABSOLUTE_ZERO);
+            self.grid.set_temperature(x, y, temperature);
+            self.grid.mark_changed(x, y);
+        }
+    }
+
+    /// Rows changed since the last call, for renderers that only redraw what moved.
+    pub fn take_dirty_rows(&mut self) -> Vec<Range<usize>> {
+        self.grid.take_dirty_rows()
+    }
+
+    /// Flags every row as changed, for renderers whose way of drawing cells changed.
+    pub fn redraw_all(&mut self) {
+        self.grid.redraw_all();
+    }
+
+    /// Chunk of the unbounded world that the bottom left corner of the grid shows. The grid only
+    /// ever simulates a window onto that world, moved around with [`Simulation::shift`].
+    pub fn origin(&self) -> ChunkCoord {
+        self.origin
+    }
+
+    /// Moves the window the grid shows over the unbounded world by `dx` chunks to the right and
+    /// `dy` chunks up. Chunks that leave the window are saved to `store`, and those entering it
+    /// are loaded from there, or start out empty if they were never visited. Chunks outside the
+    /// window are not simulated until they come back into it.
+    ///
+    /// Every rigid body is released first, and every chunk is woken afterwards. A chunk that
+    /// could not be saved is kept in memory by the store, and one that could not be loaded
+    /// starts out empty; either way the first such error is returned once the shift is done.
+    ///
+    /// # Panics
+    ///
+    /// If the grid is not made of whole chunks of [`CHUNK_CELLS`] cells.
+    pub fn shift(&mut self, dx: i64, dy: i64, store: &mut ChunkStore) -> io::Result<()> {
+        let (width, height) = (self.grid.width(), self.grid.height());
+        assert!(
+            width % CHUNK_CELLS == 0 && height % CHUNK_CELLS == 0,
+            "only a grid of whole chunks can be shifted"
+        );
+        if (dx, dy) == (0, 0) {
+            return Ok(());
+        }
+        self.bodies.release_all(&mut self.grid);
+
+        let columns = self.grid.chunks().columns() as i64;
+        let rows = self.grid.chunks().rows() as i64;
+        let inside =
+            |column: i64, row: i64| (0..columns).contains(&column) && (0..rows).contains(&row);
+        let mut grid = Grid::new(width, height);
+        grid.set_boundaries(self.grid.boundaries());
+        let mut result = Ok(());
+
+        for row in 0..rows {
+            for column in 0..columns {
+                let from = self.grid.chunks().bounds(column as usize, row as usize);
+                if !inside(column - dx, row - dy) {
+                    let coord = (self.origin.0 + column, self.origin.1 + row);
+                    result = result.and(store.save_chunk(coord, &self.grid, &self.elements, from));
+                    continue;
+                }
+                let to = grid
+                    .chunks()
+                    .bounds((column - dx) as usize, (row - dy) as usize);
+                for y in 0..CHUNK_CELLS {
+                    for x in 0..CHUNK_CELLS {
+                        let cell = self.grid.set(from.min_x + x, from.min_y + y, None);
+                        grid.set(to.min_x + x, to.min_y + y, cell);
+                    }
+                }
+            }
+        }
+
+        self.origin = (self.origin.0 + dx, self.origin.1 + dy);
+        for row in 0..rows {
+            for column in 0..columns {
+                if !inside(column + dx, row + dy) {
+                    let coord = (self.origin.0 + column, self.origin.1 + row);
+                    let rect = grid.chunks().bounds(column as usize, row as usize);
+                    result = result.and(store.load_chunk(coord, &mut grid, &self.elements, rect));
+                }
+            }
+        }
+
+        grid.chunks_mut().wake_all();
+        grid.redraw_all();
+        self.grid = grid;
+        result
+    }
+
+    /// Saves every chunk of the window to `store`, so a store on disk holds the whole world.
+    /// The grid itself is left as it is.
+    pub fn save_window(&self, store: &mut ChunkStore) -> io::Result<()> {
+        let mut result = Ok(());
+        for (coord, rect) in self.window_chunks() {
+            result = result.and(store.save_chunk(coord, &self.grid, &self.elements, rect));
+        }
+        result
+    }
+
+    /// Replaces every chunk of the window that `store` holds with the saved one, such as when
+    /// picking up a world saved in an earlier session.
+    pub fn load_window(&mut self, store: &mut ChunkStore) -> io::Result<()> {
+        self.bodies.release_all(&mut self.grid);
+        let mut result = Ok(());
+        for (coord, rect) in self.window_chunks() {
+            result = result.and(store.load_chunk(coord, &mut self.grid, &self.elements, rect));
+        }
+        self.grid.chunks_mut().wake_all();
+        self.grid.redraw_all();
+        result
+    }
+
+    /// World coordinates and grid bounds of every chunk of the window.
+    fn window_chunks(&self) -> Vec<(ChunkCoord, Rect)> {
+        let chunks = self.grid.chunks();
+        (0..chunks.rows())
+            .flat_map(|row| (0..chunks.columns()).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                let coord = (self.origin.0 + column as i64, self.origin.1 + row as i64);
+                (coord, chunks.bounds(column, row))
+            })
+            .collect()
+    }
+
+    /// Advances the simulation by one tick. Only the dirty rectangles of awake chunks are
+    /// simulated; chunks where nothing changed during the previous tick are skipped.
+    ///
+    /// Chunks are updated in four checkerboard phases. Chunks within a phase are two chunks
+    /// apart, and no particle reaches further than [`MAX_REACH`](crate::simulation::MAX_REACH)
+    /// cells out of its chunk, so the chunks of a phase are updated in parallel. Chunks that
+    /// could reach across a wrapping edge are updated one by one afterwards. Structures are
+    /// then checked for overloaded solids, rigid bodies moved, and pressure and heat balanced,
+    /// across the whole grid.
+    pub fn step(&mut self) {
+        self.tick += 1;
+        self.grid.chunks_mut().advance();
+
+        for phase in 0..4 {
+            let chunks = self.grid.chunks().phase(phase);
+            let (grid, elements, behaviors) = (&self.grid, &self.elements, &self.behaviors);
+            let (seed, tick) = (self.seed, self.tick);
+
+            let changed: Vec<Vec<(usize, usize)>> = self.thread_pool.install(|| {
+                chunks
+                    .par_iter()
+                    .map(|&(index, rect)| {
+                        let mut rng = chunk_rng(seed, tick, index);
+                        update_chunk(grid, elements, behaviors, rect, &mut rng, tick)
+                    })
+                    .collect()
+            });
+
+            for (x, y) in changed.into_iter().flatten() {
+                self.grid.mark_changed(x, y);
+            }
+        }
+
+        for (index, rect) in self.grid.chunks().seams() {
+            let mut rng = chunk_rng(self.seed, self.tick, index);
+            let (grid, elements, behaviors) = (&self.grid, &self.elements, &self.behaviors);
+            let changed = update_chunk(grid, elements, behaviors, rect, &mut rng, self.tick);
+            for (x, y) in changed {
+                self.grid.mark_changed(x, y);
+            }
+        }
+
+        self.settle_structures();
+        self.bodies.update(&mut self.grid, &self.elements);
+        pressure::balance(
+            &mut self.grid,
+            &self.elements,
+            &mut self.pressure,
+            &mut self.rng,
+        );
+        self.exchange_heat();
+    }
+
+    /// Spreads stress through the solids of every awake chunk and crumbles those loaded past
+    /// their strength. New stresses are computed in parallel from the current ones, then
+    /// written back together.
+    fn settle_structures(&mut self) {
+        let chunks = self.grid.chunks().awake();
+        let (grid, elements) = (&self.grid, &self.elements);
+
+        let stresses: Vec<Vec<(usize, usize, u16)>> = self.thread_pool.install(|| {
+            chunks
+                .par_iter()
+                .map(|&(_, rect)| structure::settle(grid, elements, rect))
+                .collect()
+        });
+
+        for (x, y, stress) in stresses.into_iter().flatten() {
+            self.grid.set_stress(x, y, stress);
+            // Keep the neighbours awake so the change spreads next tick
+            self.grid.chunks_mut().wake(x, y);
+            self.crumble_if_overloaded(x, y);
+        }
+    }
+
+    /// Turns the particle at `(x, y)` into its debris if its stress exceeds its strength.
+    fn crumble_if_overloaded(&mut self, x: usize, y: usize) {
+        let Some(cell) = self.grid.get(x, y) else {
+            return;
+        };
+        let id = cell.element;
+        let overloaded = self
+            .elements
+            .get(id)
+            .structure
+            .as_ref()
+            .is_some_and(|structure| cell.stress > structure.strength);
+        if !overloaded {
+            return;
+        }
+        match self.elements.crumbles_into(id) {
+            Some(debris) => {
+                if let Some(cell) = self.grid.get_mut(x, y) {
+                    cell.transform(&self.elements, debris, &mut self.rng);
+                }
+            }
+            None => {
+                self.grid.set(x, y, None);
+            }
+        }
+        self.grid.mark_changed(x, y);
+    }
+
+    /// Conducts heat between neighbouring cells of every awake or warm chunk. New temperatures
+    /// are computed in parallel from the current ones, then written back together.
+    fn exchange_heat(&mut self) {
+        let chunks = self.grid.chunks().thermal();
+        let (grid, elements) = (&self.grid, &self.elements);
+
+        let temperatures: Vec<Vec<(usize, usize, f32)>> = self.thread_pool.install(|| {
+            chunks
+                .par_iter()
+                .map(|&(_, bounds)| heat::conduct(grid, elements, bounds))
+                .collect()
+        });
+
+        for (&(index, _), temperatures) in chunks.iter().zip(temperatures) {
+            let mut settling = false;
+            for (x, y, temperature) in temperatures {
+                let previous = self
+                    .grid
+                    .get(x, y)
+                    .map_or(temperature, |cell| cell.temperature);
+                settling |= (temperature - previous).abs() > heat::HEAT_EPSILON;
+                self.grid.set_temperature(x, y, temperature);
+                self.apply_phase_transition(x, y);
+                self.ignite_if_hot(x, y);
+            }
+            if settling {
+                self.grid.chunks_mut().keep_warm(index);
+            }
+        }
+    }
+}
+
+/// Where a particle wants to go this tick, and the velocity and direction it leaves with.
+struct Move {
+    from: (usize, usize),
+    motion: Motion,
+    velocity: Velocity,
+    direction: i8,
+}
+
+/// A reaction that fired between the particle at `a` and its neighbour at `b`.
+struct Contact<'a> {
+    a: (usize, usize),
+    b: (usize, usize),
+    reaction: &'a ReactionRule,
+}
+
+/// Whether a particle touches anything it can react with.
+enum Touch<'a> {
+    Inert,
+    /// A reaction is possible but did not fire this tick.
+    Waiting,
+    Reacting(Contact<'a>),
+}
+
+const NEIGHBOURS: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+const SURROUNDING: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+impl Simulation {
+    /// Converts the particle at `(x, y)` in place if its temperature crossed one of its
+    /// element's thresholds.
+    fn apply_phase_transition(&mut self, x: usize, y: usize) {
+        let Some(into) = self
+            .grid
+            .get(x, y)
+            .and_then(|cell| self.elements.transition_at(cell.element, cell.temperature))
+        else {
+            return;
+        };
+        if let Some(cell) = self.grid.get_mut(x, y) {
+            cell.transform(&self.elements, into, &mut self.rng);
+        }
+        self.grid.mark_changed(x, y);
+    }
+
+    /// Sets the particle at `(x, y)` alight if heat alone brought it to its ignition temperature.
+    fn ignite_if_hot(&mut self, x: usize, y: usize) {
+        if let Some(cell) = self.grid.get_mut(x, y) {
+            if fire::is_hot_enough(&self.elements, cell) {
+                fire::ignite(&self.elements, cell);
+                self.grid.mark_changed(x, y);
+            }
+        }
+    }
+}
+
+fn build_thread_pool(threads: usize) -> ThreadPool {
+    ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .expect("failed to start simulation threads")
+}
+
+/// Moves and reacts the particles in the dirty rectangle of one chunk and returns the cells
+/// that changed.
+fn update_chunk(
+    grid: &Grid,
+    elements: &ElementRegistry,
+    behaviors: &Behaviors,
+    rect: Rect,
+    rng: &mut SimRng,
+    tick: u64,
+) -> Vec<(usize, usize)> {
+    // SAFETY: ageing stays inside the chunk and burning reaches one cell out of it, see the
+    // moves below
+    let mut changed = unsafe { decay::decay(grid, elements, rect, rng) };
+    changed.extend(unsafe { fire::burn(grid, elements, rect, rng, tick) });
+
+    let mut moves = Vec::new();
+    let mut edits = Vec::new();
+    let mut contacts = Vec::new();
+    let mut waiting = Vec::new();
+
+    // Determine moves
+    for y in rect.min_y..rect.max_y {
+        for x in rect.min_x..rect.max_x {
+            let Some(cell) = grid.get(x, y) else {
+                continue;
+            };
+            if cell.last_update == tick {
+                continue;
+            }
+            match find_reaction(grid, elements, x, y, rng) {
+                Touch::Reacting(contact) => {
+                    // A reacting particle stays put this tick
+                    contacts.push(contact);
+                    continue;
+                }
+                // Keep the chunk awake so the pair gets another roll next tick
+                Touch::Waiting => waiting.push((x, y)),
+                Touch::Inert => {}
+            }
+            let element = elements.get(cell.element);
+
+            let (motion, velocity, direction) = if element.element_type
+                != ElementType::ImmovableSolid
+                && displacement_below(grid, elements, x, y, element) > 0.0
+            {
+                // Try to sink into the lighter fluid below; the swap is resolved when applying
+                (Motion::MoveBy((0, -1)), cell.velocity, cell.direction)
+            } else if let Some(behavior) = behaviors.get(cell.element) {
+                let mut ctx = ParticleContext::new(grid, elements, rng, (x, y), cell, &mut edits);
+                behavior.update(&mut ctx);
+                ctx.finish()
+            } else {
+                continue;
+            };
+
+            if motion != Motion::Stay || velocity != cell.velocity || direction != cell.direction {
+                moves.push(Move {
+                    from: (x, y),
+                    motion,
+                    velocity,
+                    direction,
+                });
+            }
+        }
+    }
+
+    // Spawns and transforms go first, while every particle is still where it was looked at
+    for edit in edits {
+        // SAFETY: behaviours cannot address cells beyond `MAX_REACH`, see the moves below
+        if let Some(position) = unsafe { apply_edit(grid, elements, edit, rng, tick) } {
+            changed.push(position);
+        }
+    }
+
+    // Shuffle the moves to prevent bias
+    moves.shuffle(rng);
+
+    // Apply moves, tracing the path again as earlier moves may have blocked it
+    changed.extend(waiting);
+    for Move {
+        from,
+        motion,
+        velocity,
+        direction,
+    } in moves
+    {
+        // The particle was already carried off by a displacement swap, and whatever now sits
+        // at `from` has already been moved this tick
+        if !is_unchanged(grid, from, tick) {
+            continue;
+        }
+        let requested = match motion {
+            Motion::Stay => (0, 0),
+            Motion::MoveBy(offset) => offset,
+            Motion::SwapWith(to) => {
+                // SAFETY: behaviours cannot address cells beyond `MAX_REACH`, see the move below
+                if unsafe { swap_unchanged(grid, from, to, velocity, direction, tick) } {
+                    changed.push(to);
+                    changed.push(from);
+                }
+                continue;
+            }
+        };
+        let reached = grid.trace(from, requested.0, requested.1);
+        if reached == (0, 0) && requested == (0, -1) && from.1 > 0 {
+            // Blocked straight down: heavier particles may trade places with a lighter fluid
+            let below = (from.0, from.1 - 1);
+            let chance = grid.get(from.0, from.1).map_or(0.0, |cell| {
+                displacement_below(grid, elements, from.0, from.1, elements.get(cell.element))
+            });
+            if chance > 0.0 {
+                if is_unchanged(grid, below, tick) && rng.gen_bool(chance) {
+                    // SAFETY: the cell right below is within this chunk's reach, see the move below
+                    unsafe { grid.swap_cells_shared(from, below, tick) };
+                    changed.push(below);
+                }
+                // Either way stay awake so the particle keeps trying to sink
+                changed.push(from);
+                continue;
+            }
+        }
+
+        // SAFETY: both cells are within `MAX_REACH` of this chunk, and no other chunk of the
+        // current phase reaches that far, so no other thread can be touching them.
+        unsafe {
+            grid.with_cell_shared(from.0, from.1, |cell| {
+                if let Some(cell) = cell {
+                    cell.velocity = velocity;
+                    cell.direction = direction;
+                }
+            });
+            match grid.locate(from.0 as isize + reached.0, from.1 as isize + reached.1) {
+                Location::Inside(to) if to != from => {
+                    grid.move_cell_shared(from, to, tick);
+                    changed.push(to);
+                }
+                Location::Void => {
+                    // Left through an open edge
+                    grid.with_cell_shared(from.0, from.1, |cell| *cell = None);
+                }
+                Location::Inside(_) | Location::Wall => {}
+            }
+        }
+        changed.push(from);
+    }
+
+    for contact in contacts {
+        react(grid, elements, contact, rng, tick, &mut changed);
+    }
+    changed
+}
+
+/// Rolls the reactions between the particle at `(x, y)` and each of its direct neighbours, and
+/// returns the first that fires.
+fn find_reaction<'a>(
+    grid: &Grid,
+    elements: &'a ElementRegistry,
+    x: usize,
+    y: usize,
+    rng: &mut SimRng,
+) -> Touch<'a> {
+    let Some(cell) = grid.get(x, y) else {
+        return Touch::Inert;
+    };
+    let candidates = elements.reactions_of(cell.element);
+    let mut touch = Touch::Inert;
+    for b in neighbours(grid, (x, y), &NEIGHBOURS) {
+        let Some(other) = grid.get(b.0, b.1) else {
+            continue;
+        };
+        for reaction in candidates
+            .iter()
+            .filter(|reaction| reaction.reactants[1] == other.element)
+        {
+            if !has_catalyst(grid, reaction, (x, y), b) {
+                continue;
+            }
+            if rng.gen_bool(reaction.probability) {
+                return Touch::Reacting(Contact {
+                    a: (x, y),
+                    b,
+                    reaction,
+                });
+            }
+            touch = Touch::Waiting;
+        }
+    }
+    touch
+}
+
+fn has_catalyst(
+    grid: &Grid,
+    reaction: &ReactionRule,
+    a: (usize, usize),
+    b: (usize, usize),
+) -> bool {
+    reaction.catalysts.is_empty()
+        || neighbours(grid, a, &SURROUNDING)
+            .chain(neighbours(grid, b, &SURROUNDING))
+            .filter_map(|(x, y)| grid.get(x, y))
+            .any(|cell| reaction.catalysts.contains(&cell.element))
+}
+
+fn neighbours<'a>(
+    grid: &'a Grid,
+    (x, y): (usize, usize),
+    offsets: &'a [(isize, isize)],
+) -> impl Iterator<Item = (usize, usize)> + 'a {
+    offsets
+        .iter()
+        .filter_map(move |&(dx, dy)| grid.neighbour((x, y), dx, dy))
+}
+
+/// Replaces the reactants of `contact` with its products and releases its by-products, unless
+/// one of the reactants moved away or changed while the moves were applied.
+fn react(
+    grid: &Grid,
+    elements: &ElementRegistry,
+    Contact { a, b, reaction }: Contact,
+    rng: &mut SimRng,
+    tick: u64,
+    changed: &mut Vec<(usize, usize)>,
+) {
+    let still_there = [a, b]
+        .into_iter()
+        .zip(&reaction.reactants)
+        .all(|((x, y), reactant)| grid.get(x, y).is_some_and(|cell| cell.element == *reactant));
+    if !still_there {
+        return;
+    }
+
+    for ((position, reactant), product) in [a, b]
+        .into_iter()
+        .zip(&reaction.reactants)
+        .zip(&reaction.products)
+    {
+        if *product == Some(*reactant) {
+            continue;
+        }
+        let cell = product.map(|product| Cell::spawn(elements, product, rng));
+        // SAFETY: reactants are direct neighbours, well within this chunk's reach
+        unsafe { place_shared(grid, position, cell, tick) };
+        changed.push(position);
+    }
+
+    for byproduct in &reaction.byproducts {
+        let Some(position) = [a, b]
+            .into_iter()
+            .chain(neighbours(grid, a, &SURROUNDING))
+            .find(|&(x, y)| grid.is_empty(x, y))
+        else {
+            break;
+        };
+        let cell = Cell::spawn(elements, *byproduct, rng);
+        // SAFETY: by-products land next to the first reactant, within this chunk's reach
+        unsafe { place_shared(grid, position, Some(cell), tick) };
+        changed.push(position);
+    }
+}
+
+/// Carries out an edit requested by a behaviour and returns the cell it changed or woke, if it
+/// still applied.
+///
+/// # Safety
+///
+/// Same contract as [`Grid::with_cell_shared`].
+unsafe fn apply_edit(
+    grid: &Grid,
+    elements: &ElementRegistry,
+    edit: Edit,
+    rng: &mut SimRng,
+    tick: u64,
+) -> Option<(usize, usize)> {
+    match edit {
+        Edit::Spawn(position, element) => {
+            if !grid.is_empty(position.0, position.1) {
+                return None;
+            }
+            let cell = Cell::spawn(elements, element, rng);
+            place_shared(grid, position, Some(cell), tick);
+            Some(position)
+        }
+        Edit::Transform(position, element) => {
+            grid.with_cell_shared(position.0, position.1, |slot| {
+                let cell = slot.as_mut()?;
+                cell.transform(elements, element, rng);
+                cell.last_update = tick;
+                Some(position)
+            })
+        }
+        Edit::Remove(position) => {
+            grid.with_cell_shared(position.0, position.1, |slot| slot.take().map(|_| position))
+        }
+        Edit::Wake(position) => Some(position),
+    }
+}
+
+/// Swaps the particle at `from` with the content of `to` and gives it `velocity` and `direction`,
+/// unless either cell was already changed this tick. Returns whether the swap happened.
+///
+/// # Safety
+///
+/// Same contract as [`Grid::swap_cells_shared`].
+unsafe fn swap_unchanged(
+    grid: &Grid,
+    from: (usize, usize),
+    to: (usize, usize),
+    velocity: Velocity,
+    direction: i8,
+    tick: u64,
+) -> bool {
+    if grid.is_empty(from.0, from.1)
+        || !is_unchanged(grid, from, tick)
+        || !is_unchanged(grid, to, tick)
+    {
+        return false;
+    }
+    grid.with_cell_shared(from.0, from.1, |cell| {
+        if let Some(cell) = cell {
+            cell.velocity = velocity;
+            cell.direction = direction;
+        }
+    });
+    grid.swap_cells_shared(from, to, tick);
+    true
+}
+
+/// Whether the cell at `(x, y)` is empty or holds a particle that has not moved or changed
+/// during `tick`.
+fn is_unchanged(grid: &Grid, (x, y): (usize, usize), tick: u64) -> bool {
+    grid.get(x, y).is_none_or(|cell| cell.last_update != tick)
+}
+
+/// Puts `cell` at `position`, stamped with `tick` so it does not move again this tick.
+///
+/// # Safety
+///
+/// Same contract as [`Grid::with_cell_shared`].
+pub(crate) unsafe fn place_shared(
+    grid: &Grid,
+    position: (usize, usize),
+    cell: Option<Cell>,
+    tick: u64,
+) {
+    grid.with_cell_shared(position.0, position.1, |slot| {
+        *slot = cell.map(|mut cell| {
+            cell.last_update = tick;
+            cell
+        });
+    });
+}
+
+/// Chance that the particle at `(x, y)` trades places with the cell right below it this tick.
+fn displacement_below(
+    grid: &Grid,
+    elements: &ElementRegistry,
+    x: usize,
+    y: usize,
+    element: &Element,
+) -> f64 {
+    if y == 0 {
+        return 0.0;
+    }
+    grid.get(x, y - 1).map_or(0.0, |below| {
+        element.displacement_chance(elements.get(below.element))
+    })
+}
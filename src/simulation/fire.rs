@@ -0,0 +1,141 @@
+use crate::components::element_registry::ElementRegistry;
+use crate::simulation::{cell::Cell, chunk::Rect, engine::place_shared, grid::Grid, rng::SimRng};
+use rand::Rng;
+
+/// Temperature a burning particle is kept at, so its heat spreads to its surroundings.
+pub const FLAME_TEMPERATURE: f32 = 900.0;
+
+/// Chance per tick that a burning fuel gives off its flame into the empty cell above it.
+const FLAME_CHANCE: f64 = 0.3;
+
+/// Chance per tick that a burning fuel gives off its smoke into the empty cell above it.
+const SMOKE_CHANCE: f64 = 0.05;
+
+/// Burns the particles in `rect` for one tick: particles hot enough catch fire, burning ones
+/// set their flammable neighbours alight, give off flames and smoke, and burn out into their
+/// remains. Flames, smoke and remains are stamped with `tick`, so they are not updated again
+/// this tick. Returns the cells that changed.
+///
+/// # Safety
+///
+/// Writes cells up to one cell outside `rect` through a shared reference, so the caller must
+/// guarantee that no other thread touches that area during the call, as for
+/// [`Grid::move_cell_shared`].
+pub(crate) unsafe fn burn(
+    grid: &Grid,
+    elements: &ElementRegistry,
+    rect: Rect,
+    rng: &mut SimRng,
+    tick: u64,
+) -> Vec<(usize, usize)> {
+    let mut igniting = Vec::new();
+    let mut burning = Vec::new();
+
+    for y in rect.min_y..rect.max_y {
+        for x in rect.min_x..rect.max_x {
+            let Some(cell) = grid.get(x, y) else {
+                continue;
+            };
+            if cell.burning.is_none() {
+                if is_hot_enough(elements, cell) {
+                    igniting.push((x, y));
+                }
+                continue;
+            }
+            burning.push((x, y));
+
+            for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                let Some((nx, ny)) = grid.neighbour((x, y), dx, dy) else {
+                    continue;
+                };
+                let Some(neighbour) = grid.get(nx, ny) else {
+                    continue;
+                };
+                let flammability = elements
+                    .get(neighbour.element)
+                    .combustion
+                    .as_ref()
+                    .map_or(0.0, |combustion| combustion.flammability);
+                if neighbour.burning.is_none() && flammability > 0.0 && rng.gen_bool(flammability) {
+                    igniting.push((nx, ny));
+                }
+            }
+        }
+    }
+
+    let mut changed = Vec::new();
+    for (x, y) in igniting {
+        grid.with_cell_shared(x, y, |slot| {
+            if let Some(cell) = slot {
+                ignite(elements, cell);
+            }
+        });
+        changed.push((x, y));
+    }
+
+    for (x, y) in burning {
+        let (left, element) = grid.with_cell_shared(x, y, |slot| {
+            let cell = slot
+                .as_mut()
+                .expect("burning particles stay in place during the burn pass");
+            let left = cell.burning.unwrap_or(0).saturating_sub(1);
+            cell.burning = Some(left);
+            cell.temperature = cell.temperature.max(FLAME_TEMPERATURE);
+            // Flames that decay keep fading out while they flicker
+            let alpha = cell.lifetime.map_or(255, |_| cell.color[3]);
+            cell.color = flame_color(rng, alpha);
+            (left, cell.element)
+        });
+        changed.push((x, y));
+
+        if left == 0 {
+            let remains = elements
+                .burns_into(element)
+                .map(|remains| Cell::spawn(elements, remains, rng));
+            place_shared(grid, (x, y), remains, tick);
+        } else if let Some(above) = grid
+            .neighbour((x, y), 0, 1)
+            .filter(|&(x, y)| grid.is_empty(x, y))
+        {
+            let emission = if rng.gen_bool(FLAME_CHANCE) {
+                elements.emits_flame(element)
+            } else if rng.gen_bool(SMOKE_CHANCE) {
+                elements.emits_smoke(element)
+            } else {
+                None
+            };
+            if let Some(emission) = emission {
+                let cell = Cell::spawn(elements, emission, rng);
+                place_shared(grid, above, Some(cell), tick);
+                changed.push(above);
+            }
+        }
+    }
+    changed
+}
+
+/// Whether the particle is flammable, not yet burning and at or above its ignition temperature.
+pub fn is_hot_enough(elements: &ElementRegistry, cell: &Cell) -> bool {
+    cell.burning.is_none()
+        && elements
+            .get(cell.element)
+            .combustion
+            .as_ref()
+            .is_some_and(|combustion| cell.temperature >= combustion.ignition_temperature)
+}
+
+/// Sets the particle alight for its element's burn duration, unless it is already burning or
+/// cannot burn.
+pub fn ignite(elements: &ElementRegistry, cell: &mut Cell) {
+    if cell.burning.is_none() {
+        cell.burning = elements
+            .get(cell.element)
+            .combustion
+            .as_ref()
+            .map(|combustion| combustion.burn_duration);
+    }
+}
+
+fn flame_color(rng: &mut SimRng, alpha: u8) -> [u8; 4] {
+    [255, rng.gen_range(60..=200), 0, alpha]
+}
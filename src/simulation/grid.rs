@@ -0,0 +1,307 @@
+use crate::simulation::{
+    boundary::{resolve_axis, Boundaries, Location},
+    cell::Cell,
+    chunk::Chunks,
+};
+use std::cell::UnsafeCell;
+use std::collections::VecDeque;
+use std::ops::Range;
+
+/// Cell storage for the simulation. Cells live in one contiguous array indexed by
+/// `y * width + x`, with `y = 0` at the bottom.
+///
+/// Each cell sits in its own `UnsafeCell` so that chunk workers can move particles through a
+/// shared reference while the simulation guarantees they never touch the same cells.
+pub struct Grid {
+    width: usize,
+    height: usize,
+    cells: Vec<UnsafeCell<Option<Cell>>>,
+    dirty_rows: Vec<bool>,
+    chunks: Chunks,
+    boundaries: Boundaries,
+}
+
+impl Grid {
+    pub fn new(width: usize, height: usize) -> Self {
+        Grid {
+            width,
+            height,
+            cells: (0..width * height).map(|_| UnsafeCell::new(None)).collect(),
+            dirty_rows: vec![false; height],
+            chunks: Chunks::new(width, height),
+            boundaries: Boundaries::default(),
+        }
+    }
+
+    pub fn boundaries(&self) -> Boundaries {
+        self.boundaries
+    }
+
+    /// Sets what happens at each edge, wrapping both ends of an axis if either wraps, see
+    /// [`Boundaries::paired`].
+    pub fn set_boundaries(&mut self, boundaries: Boundaries) {
+        let boundaries = boundaries.paired();
+        self.boundaries = boundaries;
+        self.chunks.set_wrap(
+            boundaries.wraps_horizontally(),
+            boundaries.wraps_vertically(),
+        );
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn chunks(&self) -> &Chunks {
+        &self.chunks
+    }
+
+    pub fn chunks_mut(&mut self) -> &mut Chunks {
+        &mut self.chunks
+    }
+
+    pub fn index(&self, x: usize, y: usize) -> usize {
+        y * self.width + x
+    }
+
+    pub fn is_in_bounds(&self, x: isize, y: isize) -> bool {
+        x >= 0 && x < self.width as isize && y >= 0 && y < self.height as isize
+    }
+
+    /// What lies at `(x, y)`, wrapping around or running into the edges of the grid according
+    /// to its [`Boundaries`]. A position past both a solid and an open edge is a wall.
+    pub fn locate(&self, x: isize, y: isize) -> Location {
+        let Boundaries {
+            left,
+            right,
+            bottom,
+            top,
+        } = self.boundaries;
+        match (
+            resolve_axis(x, self.width, left, right),
+            resolve_axis(y, self.height, bottom, top),
+        ) {
+            (Ok(x), Ok(y)) => Location::Inside((x, y)),
+            (Err(Location::Wall), _) | (_, Err(Location::Wall)) => Location::Wall,
+            _ => Location::Void,
+        }
+    }
+
+    /// The cell at offset `(dx, dy)` from `from`, or `None` if that lies beyond a solid or open
+    /// edge.
+    pub fn neighbour(&self, from: (usize, usize), dx: isize, dy: isize) -> Option<(usize, usize)> {
+        match self.locate(from.0 as isize + dx, from.1 as isize + dy) {
+            Location::Inside(position) => Some(position),
+            Location::Wall | Location::Void => None,
+        }
+    }
+
+    /// Walks from `from` towards offset `(dx, dy)` one cell at a time and returns the offset of
+    /// the last empty cell before the path hits a solid edge or another particle, so fast
+    /// particles never pass through obstacles. A path that crosses an open edge ends at the
+    /// first offset beyond it.
+    pub fn trace(&self, from: (usize, usize), dx: isize, dy: isize) -> (isize, isize) {
+        let steps = dx.abs().max(dy.abs());
+        let mut last = (0, 0);
+        for step in 1..=steps {
+            let offset = (dx * step / steps, dy * step / steps);
+            match self.locate(from.0 as isize + offset.0, from.1 as isize + offset.1) {
+                Location::Inside((x, y)) if self.is_empty(x, y) => last = offset,
+                Location::Inside(_) | Location::Wall => break,
+                Location::Void => return offset,
+            }
+        }
+        last
+    }
+
+    /// Collects the cells 4-connected to `start`, also across wrapping edges, for which `include` holds, marking them in
+    /// `visited`, which is indexed like the grid. Returns `None` once there are more than
+    /// `limit` of them.
+    pub fn flood(
+        &self,
+        visited: &mut [bool],
+        start: (usize, usize),
+        limit: usize,
+        include: impl Fn(&Grid, usize, usize) -> bool,
+    ) -> Option<Vec<(usize, usize)>> {
+        let mut cells = Vec::new();
+        let mut queue = VecDeque::from([start]);
+        visited[self.index(start.0, start.1)] = true;
+
+        while let Some((x, y)) = queue.pop_front() {
+            cells.push((x, y));
+            if cells.len() > limit {
+                return None;
+            }
+            for (dx, dy) in [(0, -1), (-1, 0), (1, 0), (0, 1)] {
+                let Some((nx, ny)) = self.neighbour((x, y), dx, dy) else {
+                    continue;
+                };
+                let index = self.index(nx, ny);
+                if !visited[index] && include(self, nx, ny) {
+                    visited[index] = true;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        Some(cells)
+    }
+
+    pub fn is_empty(&self, x: usize, y: usize) -> bool {
+        self.get(x, y).is_none()
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&Cell> {
+        // SAFETY: cells are only written through `&self` by `move_cell_shared`,
+        // `swap_cells_shared` and `with_cell_shared`, whose callers guarantee that no other
+        // reference to those cells exists at the time, see the `Sync` impl below.
+        unsafe { (*self.cells[self.index(x, y)].get()).as_ref() }
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut Cell> {
+        let index = self.index(x, y);
+        self.cells[index].get_mut().as_mut()
+    }
+
+    /// Replaces the content of a cell, returning what was there before.
+    pub fn set(&mut self, x: usize, y: usize, cell: Option<Cell>) -> Option<Cell> {
+        let index = self.index(x, y);
+        self.mark_changed(x, y);
+        std::mem::replace(self.cells[index].get_mut(), cell)
+    }
+
+    /// Moves the content of `from` into `to`, leaving `from` empty.
+    pub fn move_cell(&mut self, from: (usize, usize), to: (usize, usize)) {
+        let from_index = self.index(from.0, from.1);
+        let to_index = self.index(to.0, to.1);
+        let cell = self.cells[from_index].get_mut().take();
+        *self.cells[to_index].get_mut() = cell;
+        self.mark_changed(from.0, from.1);
+        self.mark_changed(to.0, to.1);
+    }
+
+    /// Swaps the contents of two cells.
+    pub fn swap(&mut self, a: (usize, usize), b: (usize, usize)) {
+        let a_index = self.index(a.0, a.1);
+        let b_index = self.index(b.0, b.1);
+        self.cells.swap(a_index, b_index);
+        self.mark_changed(a.0, a.1);
+        self.mark_changed(b.0, b.1);
+    }
+
+    /// Moves the particle at `from` into `to` through a shared reference and stamps it with
+    /// `tick`. Dirty tracking is left to the caller, which must call [`Grid::mark_changed`] for
+    /// both cells once it has exclusive access again.
+    ///
+    /// # Safety
+    ///
+    /// No other reference to the `from` or `to` cells may exist, on this or any other thread,
+    /// for the duration of the call.
+    pub unsafe fn move_cell_shared(&self, from: (usize, usize), to: (usize, usize), tick: u64) {
+        let from_cell = &mut *self.cells[self.index(from.0, from.1)].get();
+        let to_cell = &mut *self.cells[self.index(to.0, to.1)].get();
+        *to_cell = from_cell.take();
+        if let Some(cell) = to_cell {
+            cell.last_update = tick;
+        }
+    }
+
+    /// Swaps the particles at `a` and `b` through a shared reference and stamps both with
+    /// `tick`.
+    ///
+    /// # Safety
+    ///
+    /// Same contract as [`Grid::move_cell_shared`].
+    pub unsafe fn swap_cells_shared(&self, a: (usize, usize), b: (usize, usize), tick: u64) {
+        let a = &mut *self.cells[self.index(a.0, a.1)].get();
+        let b = &mut *self.cells[self.index(b.0, b.1)].get();
+        std::mem::swap(a, b);
+        for cell in [a, b].into_iter().flatten() {
+            cell.last_update = tick;
+        }
+    }
+
+    /// Runs `f` on the cell at `(x, y)` through a shared reference.
+    ///
+    /// # Safety
+    ///
+    /// Same contract as [`Grid::move_cell_shared`]: no other reference to the cell may exist
+    /// while `f` runs.
+    pub unsafe fn with_cell_shared<R>(
+        &self,
+        x: usize,
+        y: usize,
+        f: impl FnOnce(&mut Option<Cell>) -> R,
+    ) -> R {
+        f(&mut *self.cells[self.index(x, y)].get())
+    }
+
+    /// Flags every row for redrawing, for when the way cells are drawn changes.
+    pub fn redraw_all(&mut self) {
+        self.dirty_rows.fill(true);
+    }
+
+    /// Sets the temperature of the particle at `(x, y)`, flagging its row for redrawing if the
+    /// change is visible.
+    pub fn set_temperature(&mut self, x: usize, y: usize, temperature: f32) {
+        if let Some(cell) = self.get_mut(x, y) {
+            let visible = (cell.temperature - temperature).abs() > 0.5;
+            cell.temperature = temperature;
+            if visible {
+                self.dirty_rows[y] = true;
+            }
+        }
+    }
+
+    /// Records the pressure of the particle at `(x, y)`. Pressure is not drawn, so nothing is
+    /// flagged for redrawing.
+    pub fn set_pressure(&mut self, x: usize, y: usize, pressure: f32) {
+        if let Some(cell) = self.get_mut(x, y) {
+            cell.pressure = pressure;
+        }
+    }
+
+    /// Records the stress of the particle at `(x, y)`. Like pressure, it is not drawn.
+    pub fn set_stress(&mut self, x: usize, y: usize, stress: u16) {
+        if let Some(cell) = self.get_mut(x, y) {
+            cell.stress = stress;
+        }
+    }
+
+    /// Flags the cell at `(x, y)` for redrawing and wakes it and its neighbours, including those
+    /// across a wrapping edge.
+    pub fn mark_changed(&mut self, x: usize, y: usize) {
+        self.dirty_rows[y] = true;
+        self.chunks.wake(x, y);
+    }
+
+    /// Returns the rows changed since the last call, merged into contiguous ranges, and marks
+    /// them clean.
+    pub fn take_dirty_rows(&mut self) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for (y, dirty) in self.dirty_rows.iter_mut().enumerate() {
+            if !std::mem::take(dirty) {
+                continue;
+            }
+            match ranges.last_mut() {
+                Some(range) if range.end == y => range.end = y + 1,
+                _ => ranges.push(y..y + 1),
+            }
+        }
+        ranges
+    }
+}
+
+// SAFETY: the only mutations through `&self` are `move_cell_shared`, `swap_cells_shared` and
+// `with_cell_shared`, whose contracts forbid any other reference to the cells they touch while
+// they run. The simulation upholds this by updating chunks in checkerboard phases: every cell a
+// chunk worker reads or writes, whether moving, swapping (including sinking into the cell
+// below), burning, reacting or applying a behaviour's edits, lies within `MAX_REACH` of its
+// chunk, and chunks of one phase are two chunks apart, so no two workers can reach the same
+// cell. Chunks that could reach across a wrapping edge are updated one at a time after the
+// phases.
+unsafe impl Sync for Grid {}
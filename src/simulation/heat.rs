@@ -0,0 +1,56 @@
+use crate::components::element_registry::ElementRegistry;
+use crate::simulation::{cell::AMBIENT_TEMPERATURE, chunk::Rect, grid::Grid};
+
+/// Share of the temperature difference exchanged with each neighbour per tick, before the
+/// conductivity and heat capacity of the elements involved are applied.
+const CONDUCTION_RATE: f32 = 0.2;
+
+/// Most of the temperature difference a particle takes from each neighbour per tick, however
+/// low its heat capacity. Must stay below 0.25 for the exchange to remain stable.
+const MAX_EXCHANGE: f32 = 0.24;
+
+/// Conductivity of the air in empty cells, which sit at the ambient temperature.
+const AIR_CONDUCTIVITY: f32 = 0.02;
+
+/// Share of the difference to the ambient temperature every particle loses per tick.
+const AMBIENT_DRIFT: f32 = 0.001;
+
+/// Temperature change per tick below which a chunk is considered settled.
+pub const HEAT_EPSILON: f32 = 0.01;
+
+/// Computes the temperature every particle in `rect` will have after one tick of conduction,
+/// reading only the current temperatures. Returns `(x, y, temperature)` for each particle.
+pub fn conduct(grid: &Grid, elements: &ElementRegistry, rect: Rect) -> Vec<(usize, usize, f32)> {
+    let mut temperatures = Vec::new();
+
+    for y in rect.min_y..rect.max_y {
+        for x in rect.min_x..rect.max_x {
+            let Some(cell) = grid.get(x, y) else {
+                continue;
+            };
+            let element = elements.get(cell.element);
+            let mut flow = (AMBIENT_TEMPERATURE - cell.temperature) * AMBIENT_DRIFT;
+
+            for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                let Some((nx, ny)) = grid.neighbour((x, y), dx, dy) else {
+                    continue;
+                };
+                let (temperature, conductivity) = match grid.get(nx, ny) {
+                    Some(neighbour) => (
+                        neighbour.temperature,
+                        element
+                            .thermal_conductivity
+                            .min(elements.get(neighbour.element).thermal_conductivity),
+                    ),
+                    None => (AMBIENT_TEMPERATURE, AIR_CONDUCTIVITY),
+                };
+                let share =
+                    (conductivity * CONDUCTION_RATE / element.heat_capacity).min(MAX_EXCHANGE);
+                flow += (temperature - cell.temperature) * share;
+            }
+
+            temperatures.push((x, y, cell.temperature + flow));
+        }
+    }
+    temperatures
+}
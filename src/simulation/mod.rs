@@ -0,0 +1,26 @@
+pub mod behavior;
+pub mod boundary;
+pub mod cell;
+pub mod chunk;
+pub mod decay;
+pub mod engine;
+pub mod fire;
+pub mod grid;
+pub mod heat;
+pub mod pressure;
+pub mod rigid;
+pub mod rng;
+pub mod stream;
+pub mod structure;
+
+pub use behavior::*;
+pub use boundary::*;
+pub use cell::*;
+pub use chunk::*;
+pub use engine::*;
+pub use fire::*;
+pub use grid::*;
+pub use heat::*;
+pub use rigid::*;
+pub use rng::*;
+pub use stream::*;
@@ -0,0 +1,229 @@
+use crate::components::element::ElementType;
+use crate::components::element_registry::ElementRegistry;
+use crate::simulation::{boundary::Location, grid::Grid, rng::SimRng};
+use rand::Rng;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Most particles a liquid body moves from its highest surface to its lowest opening per tick.
+const LIQUID_FLOW: usize = 2;
+
+/// Largest region of empty and gas cells measured as a chamber. Anything bigger is treated as
+/// open air, which never builds pressure.
+const MAX_CHAMBER: usize = 4096;
+
+/// Share of a chamber filled with gas above which the gas starts forcing its way out.
+const GAS_BURST: f32 = 0.5;
+
+/// Chance per tick, at a completely full chamber, that a gas particle trades places with a
+/// liquid or loose solid it touches.
+const GAS_PUSH: f64 = 0.2;
+
+/// Largest liquid body levelled as a whole. Bigger bodies are left to settle by flowing, which
+/// keeps the cost of a tick bounded however much liquid there is.
+const MAX_LIQUID_BODY: usize = 16384;
+
+const NEIGHBOURS: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+/// Cells already measured by the pressure pass, kept between ticks so the pass does not have
+/// to allocate.
+#[derive(Default)]
+pub(crate) struct Visited {
+    liquids: Vec<bool>,
+    chambers: Vec<bool>,
+}
+
+impl Visited {
+    /// Marks every cell of a grid of `len` cells as not yet measured.
+    fn reset(&mut self, len: usize) {
+        for visited in [&mut self.liquids, &mut self.chambers] {
+            visited.clear();
+            visited.resize(len, false);
+        }
+    }
+}
+
+/// Balances the pressure of every liquid body and gas chamber that reaches into an awake chunk.
+///
+/// A liquid body is a connected mass of resting particles of the same element. Its pressure is
+/// the depth below its highest free surface, and while one surface stands more than a cell
+/// above another, particles are moved from the top of the highest to the lowest, as if pushed
+/// up through the column between them. Connected vessels therefore settle at the same level.
+///
+/// A chamber is a closed region of empty and gas cells, with pressure being the share of it
+/// filled with gas. Once that passes [`GAS_BURST`] the gas starts swapping places with the
+/// liquids and loose solids sealing it in.
+pub(crate) fn balance(
+    grid: &mut Grid,
+    elements: &ElementRegistry,
+    visited: &mut Visited,
+    rng: &mut SimRng,
+) {
+    let awake = grid.chunks().awake();
+    if awake.is_empty() {
+        return;
+    }
+    visited.reset(grid.width() * grid.height());
+    let Visited { liquids, chambers } = visited;
+
+    for (_, rect) in awake {
+        for y in rect.min_y..rect.max_y {
+            for x in rect.min_x..rect.max_x {
+                let Some(cell) = grid.get(x, y) else {
+                    continue;
+                };
+                let index = grid.index(x, y);
+                match elements.get(cell.element).element_type {
+                    ElementType::Liquid if !liquids[index] && is_resting(grid, x, y) => {
+                        level(grid, liquids, (x, y));
+                    }
+                    ElementType::Gas if !chambers[index] => {
+                        vent(grid, elements, chambers, (x, y), rng);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+fn is_resting(grid: &Grid, x: usize, y: usize) -> bool {
+    grid.get(x, y).is_some_and(|cell| cell.velocity.y == 0.0)
+}
+
+/// Evens out the liquid body around `start` and records its pressures, unless it is bigger
+/// than [`MAX_LIQUID_BODY`].
+fn level(grid: &mut Grid, visited: &mut [bool], start: (usize, usize)) {
+    let element = grid.get(start.0, start.1).map(|cell| cell.element);
+    let in_body = |grid: &Grid, (x, y): (usize, usize)| {
+        grid.get(x, y).map(|cell| cell.element) == element && is_resting(grid, x, y)
+    };
+    let Some(body) = grid.flood(visited, start, MAX_LIQUID_BODY, |grid, x, y| {
+        in_body(grid, (x, y))
+    }) else {
+        return;
+    };
+    let has_room_above = |grid: &Grid, position: (usize, usize)| {
+        grid.neighbour(position, 0, 1)
+            .is_some_and(|(x, y)| grid.is_empty(x, y))
+    };
+
+    let top = body.iter().map(|&(_, y)| y).max().unwrap_or(start.1);
+    for &(x, y) in &body {
+        grid.set_pressure(x, y, (top + 1 - y) as f32);
+    }
+
+    // Free surfaces: the highest give particles away, the lowest take them
+    let mut highest = BinaryHeap::new();
+    let mut lowest = BinaryHeap::new();
+    for &(x, y) in &body {
+        if has_room_above(grid, (x, y)) {
+            highest.push((y, x));
+            lowest.push(Reverse((y, x)));
+        }
+    }
+
+    for _ in 0..LIQUID_FLOW {
+        let Some((from_y, from_x)) = pop_valid(&mut highest, |&(y, x)| {
+            in_body(grid, (x, y)) && has_room_above(grid, (x, y))
+        }) else {
+            break;
+        };
+        let Some(Reverse((to_y, to_x))) = pop_valid(&mut lowest, |&Reverse((y, x))| {
+            in_body(grid, (x, y)) && has_room_above(grid, (x, y))
+        }) else {
+            break;
+        };
+        if from_y <= to_y + 1 {
+            break;
+        }
+
+        let Some(placed) = grid.neighbour((to_x, to_y), 0, 1) else {
+            break;
+        };
+        let particle = grid.set(from_x, from_y, None);
+        grid.set(placed.0, placed.1, particle);
+
+        // The particle below the one taken is now at the surface, as is the one just placed
+        let uncovered = grid
+            .neighbour((from_x, from_y), 0, -1)
+            .filter(|&below| in_body(grid, below));
+        for (x, y) in uncovered.into_iter().chain([placed]) {
+            if has_room_above(grid, (x, y)) {
+                highest.push((y, x));
+                lowest.push(Reverse((y, x)));
+            }
+        }
+    }
+}
+
+/// Pops entries off `heap` until one still satisfies `valid`.
+fn pop_valid<T: Ord>(heap: &mut BinaryHeap<T>, valid: impl Fn(&T) -> bool) -> Option<T> {
+    while let Some(entry) = heap.pop() {
+        if valid(&entry) {
+            return Some(entry);
+        }
+    }
+    None
+}
+
+/// Measures the chamber around the gas particle at `start` and lets the gas push its way out
+/// if the chamber is overfull.
+fn vent(
+    grid: &mut Grid,
+    elements: &ElementRegistry,
+    visited: &mut [bool],
+    start: (usize, usize),
+    rng: &mut SimRng,
+) {
+    let is_gas = |grid: &Grid, x: usize, y: usize| {
+        grid.get(x, y)
+            .is_some_and(|cell| elements.get(cell.element).element_type == ElementType::Gas)
+    };
+    let Some(chamber) = grid.flood(visited, start, MAX_CHAMBER, |grid, x, y| {
+        grid.is_empty(x, y) || is_gas(grid, x, y)
+    }) else {
+        return;
+    };
+    // A chamber reaching an open edge vents into the void
+    let open = chamber.iter().any(|&(x, y)| {
+        NEIGHBOURS
+            .iter()
+            .any(|&(dx, dy)| grid.locate(x as isize + dx, y as isize + dy) == Location::Void)
+    });
+    if open {
+        return;
+    }
+
+    let size = chamber.len();
+    let gas: Vec<(usize, usize)> = chamber
+        .into_iter()
+        .filter(|&(x, y)| is_gas(grid, x, y))
+        .collect();
+    let pressure = gas.len() as f32 / size as f32;
+    for &(x, y) in &gas {
+        grid.set_pressure(x, y, pressure);
+    }
+    if pressure <= GAS_BURST {
+        return;
+    }
+
+    let chance = ((pressure - GAS_BURST) / (1.0 - GAS_BURST)) as f64 * GAS_PUSH;
+    for (x, y) in gas {
+        for (dx, dy) in NEIGHBOURS {
+            let Some(neighbour) = grid.neighbour((x, y), dx, dy) else {
+                continue;
+            };
+            let pushable = grid.get(neighbour.0, neighbour.1).is_some_and(|cell| {
+                matches!(
+                    elements.get(cell.element).element_type,
+                    ElementType::Liquid | ElementType::MovableSolid
+                )
+            });
+            if pushable && rng.gen_bool(chance) {
+                grid.swap((x, y), neighbour);
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,563 @@
+use crate::components::element::ElementType;
+use crate::components::element_registry::ElementRegistry;
+use crate::simulation::{
+    boundary::Location,
+    cell::{Cell, Velocity, GRAVITY, MAX_SPEED},
+    grid::Grid,
+};
+use std::collections::{HashMap, HashSet};
+use std::f32::consts::FRAC_PI_2;
+use std::num::NonZeroU32;
+
+/// Most cells a group of immovable solids may have to come loose as a rigid body. Larger
+/// groups are terrain and never fall.
+const MAX_BODY: usize = 2048;
+
+/// Ticks a body has to lie still before it is handed back to the grid as ordinary cells.
+const REST_TICKS: u32 = 3;
+
+/// Turn, in radians per tick, of a body tipping over an edge.
+const TIP_SPEED: f32 = 0.08;
+
+/// Share of its sideways speed a body on the ground keeps per tick.
+const GROUND_FRICTION: f32 = 0.6;
+
+/// How many cells above its old place a fluid pushed aside by a body may end up.
+const DISPLACE_REACH: usize = 8;
+
+/// Identifies the rigid body a cell belongs to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BodyId(NonZeroU32);
+
+/// A connected group of immovable solids falling, turning and colliding as a unit.
+///
+/// The cells of a body stay in the grid, tagged with its ID, so that the rest of the
+/// simulation and the renderer treat them like any other particle. Every tick the body is lifted
+/// out of the grid, moved, and stamped back in. Its position is not wrapped, so a body crossing
+/// a wrapping edge stays in one piece; each cell is wrapped as it is stamped.
+pub struct RigidBody {
+    id: BodyId,
+    /// Cell offsets from the pivot, in the body's own frame.
+    shape: Vec<(isize, isize)>,
+    /// Grid cells the shape is stamped on, in the same order as `shape`.
+    stamped: Vec<(usize, usize)>,
+    /// Position of the pivot in grid coordinates.
+    position: (f32, f32),
+    velocity: Velocity,
+    angle: f32,
+    /// Turn per tick, in radians, anticlockwise.
+    spin: f32,
+    /// Average mass of the body's cells, deciding which liquids it floats on.
+    mass: f32,
+    /// Ticks the body has been lying still.
+    resting: u32,
+}
+
+/// Every rigid body in the world.
+#[derive(Default)]
+pub struct RigidBodies {
+    bodies: Vec<RigidBody>,
+    next_id: u32,
+    /// Cells already looked at while detecting new bodies, kept between ticks so detection
+    /// does not have to allocate.
+    visited: Vec<bool>,
+}
+
+impl RigidBodies {
+    /// Number of bodies currently in motion.
+    pub fn len(&self) -> usize {
+        self.bodies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bodies.is_empty()
+    }
+
+    /// Moves every body by one tick, splitting those that were cut apart and releasing those
+    /// that came to rest, then lifts out any unsupported groups of solids in awake chunks.
+    pub(crate) fn update(&mut self, grid: &mut Grid, elements: &ElementRegistry) {
+        for body in std::mem::take(&mut self.bodies) {
+            let cells = body.unstamp(grid);
+            for (mut body, cells) in self.split(grid, body, cells, elements) {
+                body.advance(grid, elements);
+                body.stamp(grid, cells);
+                if body.resting >= REST_TICKS {
+                    body.release(grid);
+                } else {
+                    self.bodies.push(body);
+                }
+            }
+        }
+        self.detect(grid, elements);
+    }
+
+    /// Stops every body where it is, handing its cells back to the grid as ordinary particles.
+    pub(crate) fn release_all(&mut self, grid: &mut Grid) {
+        for body in self.bodies.drain(..) {
+            body.release(grid);
+        }
+    }
+
+    fn next_id(&mut self) -> BodyId {
+        self.next_id += 1;
+        BodyId(NonZeroU32::new(self.next_id).expect("rigid body IDs ran out"))
+    }
+
+    /// Drops the cells of `body` that are gone and splits what is left into its connected
+    /// pieces. An intact body comes back unchanged.
+    fn split(
+        &mut self,
+        grid: &Grid,
+        body: RigidBody,
+        cells: Vec<Option<Cell>>,
+        elements: &ElementRegistry,
+    ) -> Vec<(RigidBody, Vec<Cell>)> {
+        if cells.iter().all(Option::is_some) {
+            return vec![(body, cells.into_iter().flatten().collect())];
+        }
+
+        let remaining: HashMap<(isize, isize), ((isize, isize), Cell)> = body
+            .shape
+            .iter()
+            .zip(body.pose(body.position, body.angle))
+            .zip(cells)
+            .filter_map(|((&local, position), cell)| Some((local, (position, cell?))))
+            .collect();
+        let mut pieces: Vec<Vec<(isize, isize)>> = Vec::new();
+        let mut seen = HashSet::new();
+        for &start in body
+            .shape
+            .iter()
+            .filter(|local| remaining.contains_key(local))
+        {
+            if !seen.insert(start) {
+                continue;
+            }
+            let mut piece = vec![start];
+            let mut next = 0;
+            while let Some(&(x, y)) = piece.get(next) {
+                next += 1;
+                for neighbour in [(x, y - 1), (x - 1, y), (x + 1, y), (x, y + 1)] {
+                    if remaining.contains_key(&neighbour) && seen.insert(neighbour) {
+                        piece.push(neighbour);
+                    }
+                }
+            }
+            pieces.push(piece);
+        }
+
+        // Pieces start over unrotated from where they are stamped, so nothing moves at the cut
+        let mut remaining = remaining;
+        pieces
+            .into_iter()
+            .map(|piece| {
+                let (positions, cells): (Vec<_>, Vec<_>) = piece
+                    .iter()
+                    .filter_map(|local| remaining.remove(local))
+                    .unzip();
+                let mut part = self.body_at(grid, &positions, &cells, elements);
+                part.velocity = body.velocity;
+                (part, cells)
+            })
+            .collect()
+    }
+
+    /// A new, unrotated body made of `cells` at `positions`, which are stamped cells before any
+    /// wrapping.
+    fn body_at(
+        &mut self,
+        grid: &Grid,
+        positions: &[(isize, isize)],
+        cells: &[Cell],
+        elements: &ElementRegistry,
+    ) -> RigidBody {
+        let count = positions.len() as f32;
+        let pivot = (
+            (positions.iter().map(|&(x, _)| x as f32).sum::<f32>() / count).round(),
+            (positions.iter().map(|&(_, y)| y as f32).sum::<f32>() / count).round(),
+        );
+        RigidBody {
+            id: self.next_id(),
+            shape: positions
+                .iter()
+                .map(|&(x, y)| (x - pivot.0 as isize, y - pivot.1 as isize))
+                .collect(),
+            stamped: positions
+                .iter()
+                .filter_map(|&(x, y)| match grid.locate(x, y) {
+                    Location::Inside(position) => Some(position),
+                    Location::Wall | Location::Void => None,
+                })
+                .collect(),
+            position: pivot,
+            velocity: Velocity::default(),
+            angle: 0.0,
+            spin: 0.0,
+            mass: average_mass(elements, cells),
+            resting: 0,
+        }
+    }
+
+    /// Turns every group of loose immovable solids in an awake chunk that has nothing holding
+    /// it up into a rigid body. Groups reaching across a wrapping edge are left where they are.
+    fn detect(&mut self, grid: &mut Grid, elements: &ElementRegistry) {
+        let awake = grid.chunks().awake();
+        if awake.is_empty() {
+            return;
+        }
+        let is_loose = |grid: &Grid, x: usize, y: usize| {
+            grid.get(x, y).is_some_and(|cell| {
+                cell.body.is_none()
+                    && elements.get(cell.element).element_type == ElementType::ImmovableSolid
+            })
+        };
+        let mut visited = std::mem::take(&mut self.visited);
+        visited.clear();
+        visited.resize(grid.width() * grid.height(), false);
+
+        for (_, rect) in awake {
+            for y in rect.min_y..rect.max_y {
+                for x in rect.min_x..rect.max_x {
+                    if visited[grid.index(x, y)] || !is_loose(grid, x, y) {
+                        continue;
+                    }
+                    let Some(group) = grid.flood(&mut visited, (x, y), MAX_BODY, is_loose) else {
+                        continue;
+                    };
+                    let cells: Vec<Cell> = group
+                        .iter()
+                        .filter_map(|&(x, y)| grid.get(x, y).cloned())
+                        .collect();
+                    if straddles_seam(grid, &group)
+                        || is_supported(grid, elements, &group, average_mass(elements, &cells))
+                    {
+                        continue;
+                    }
+                    let positions: Vec<(isize, isize)> = group
+                        .iter()
+                        .map(|&(x, y)| (x as isize, y as isize))
+                        .collect();
+                    let body = self.body_at(grid, &positions, &cells, elements);
+                    for &(x, y) in &group {
+                        if let Some(cell) = grid.get_mut(x, y) {
+                            cell.body = Some(body.id);
+                        }
+                    }
+                    self.bodies.push(body);
+                }
+            }
+        }
+        self.visited = visited;
+    }
+}
+
+impl RigidBody {
+    /// Takes the body's cells out of the grid. Cells that were erased, replaced or changed into
+    /// something else since the last tick come back as `None`.
+    fn unstamp(&self, grid: &mut Grid) -> Vec<Option<Cell>> {
+        self.stamped
+            .iter()
+            .map(|&(x, y)| {
+                let ours = grid
+                    .get(x, y)
+                    .is_some_and(|cell| cell.body == Some(self.id));
+                ours.then(|| grid.set(x, y, None)).flatten()
+            })
+            .collect()
+    }
+
+    /// Puts `cells` back into the grid at the body's current pose, pushing any fluid in the way
+    /// up out of it, or failing that into the nearest cell the body just left, so no fluid is
+    /// lost. Cells that ended up past an open edge are dropped from the body.
+    fn stamp(&mut self, grid: &mut Grid, cells: Vec<Cell>) {
+        let previous = std::mem::take(&mut self.stamped);
+        let pose = self.pose(self.position, self.angle);
+        let mut shape = Vec::new();
+        let mut positions = Vec::new();
+        let mut displaced = Vec::new();
+        for ((&local, (x, y)), mut cell) in self.shape.iter().zip(pose).zip(cells) {
+            let Location::Inside((x, y)) = grid.locate(x, y) else {
+                continue;
+            };
+            shape.push(local);
+            positions.push((x, y));
+            cell.body = Some(self.id);
+            if let Some(fluid) = grid.set(x, y, Some(cell)) {
+                displaced.push(((x, y), fluid));
+            }
+        }
+        // The body covers no more cells than it left, so those always have room for the fluid
+        // it pushed aside
+        for ((x, y), fluid) in displaced {
+            let free = (1..=DISPLACE_REACH as isize)
+                .filter_map(|dy| grid.neighbour((x, y), 0, dy))
+                .find(|&(x, y)| grid.is_empty(x, y))
+                .or_else(|| {
+                    previous
+                        .iter()
+                        .copied()
+                        .filter(|&(px, py)| grid.is_empty(px, py))
+                        .min_by_key(|&(px, py)| px.abs_diff(x) + py.abs_diff(y))
+                });
+            if let Some((x, y)) = free {
+                grid.set(x, y, Some(fluid));
+            }
+        }
+        self.shape = shape;
+        self.stamped = positions;
+    }
+
+    /// Hands the body's cells back to the grid as ordinary particles.
+    fn release(&self, grid: &mut Grid) {
+        for &(x, y) in &self.stamped {
+            if let Some(cell) = grid.get_mut(x, y) {
+                cell.body = None;
+            }
+        }
+    }
+
+    /// Applies one tick of gravity, then moves and turns the body as far as the grid allows.
+    fn advance(&mut self, grid: &Grid, elements: &ElementRegistry) {
+        self.velocity.y = (self.velocity.y - GRAVITY).max(-MAX_SPEED);
+        self.velocity.x = self.velocity.x.clamp(-MAX_SPEED, MAX_SPEED);
+
+        // Sweep towards the target one cell at a time so nothing is tunnelled through
+        let (dx, dy) = (self.velocity.x, self.velocity.y);
+        let steps = dx.abs().max(dy.abs()).ceil().max(1.0) as usize;
+        for step in 1..=steps {
+            let t = step as f32 / steps as f32;
+            let next = (self.position.0 + dx * t, self.position.1 + dy * t);
+            if !self.fits(grid, elements, next, self.angle) {
+                break;
+            }
+            self.position = next;
+        }
+
+        let (x, y) = self.position;
+        let grounded = !self.fits(grid, elements, (x, y - 1.0), self.angle);
+        if grounded {
+            self.velocity.y = 0.0;
+            self.spin = self.tipping(grid, elements);
+            self.velocity.x *= GROUND_FRICTION;
+            if self.velocity.x.abs() < 0.1 {
+                self.velocity.x = 0.0;
+            }
+        }
+        let side = (x + self.velocity.x.signum(), y);
+        if self.velocity.x != 0.0 && !self.fits(grid, elements, side, self.angle) {
+            self.velocity.x = 0.0;
+        }
+
+        if self.spin != 0.0 {
+            self.turn(grid, elements);
+        }
+
+        if grounded && self.spin == 0.0 && self.velocity.x == 0.0 {
+            self.resting += 1;
+        } else {
+            self.resting = 0;
+        }
+    }
+
+    /// Turns the body by its spin, shifting it towards the side it tips over if it would
+    /// otherwise dig into what it rests on. Stops the spin if there is no room.
+    fn turn(&mut self, grid: &Grid, elements: &ElementRegistry) {
+        let angle = self.angle + self.spin;
+        let towards = -self.spin.signum();
+        let (x, y) = self.position;
+        let room = [(0.0, 0.0), (towards, 0.0), (0.0, 1.0), (towards, 1.0)]
+            .into_iter()
+            .map(|(dx, dy)| (x + dx, y + dy))
+            .find(|&position| self.fits(grid, elements, position, angle));
+        match room {
+            Some(position) => {
+                self.position = position;
+                self.angle = angle;
+            }
+            None => self.spin = 0.0,
+        }
+    }
+
+    /// Spin of a grounded body: it tips over when all of its support is off to one side of its
+    /// centre of mass.
+    fn tipping(&self, grid: &Grid, elements: &ElementRegistry) -> f32 {
+        let positions = self.pose(self.position, self.angle);
+        let own: HashSet<(usize, usize)> = self.stamped.iter().copied().collect();
+        let support: Vec<f32> = positions
+            .iter()
+            .filter(|&&(x, y)| match grid.locate(x, y) {
+                Location::Inside(position) => is_held_up(grid, elements, &own, position, self.mass),
+                Location::Wall | Location::Void => false,
+            })
+            .map(|&(x, _)| x as f32)
+            .collect();
+        let centre = positions.iter().map(|&(x, _)| x as f32).sum::<f32>() / positions.len() as f32;
+
+        let left = support.iter().copied().fold(f32::INFINITY, f32::min);
+        let right = support.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        if left > centre + 0.5 {
+            TIP_SPEED
+        } else if right < centre - 0.5 {
+            -TIP_SPEED
+        } else {
+            0.0
+        }
+    }
+
+    /// Whether the body could be stamped with its pivot at `position`, turned by `angle`. The
+    /// void past an open edge never gets in the way.
+    fn fits(
+        &self,
+        grid: &Grid,
+        elements: &ElementRegistry,
+        position: (f32, f32),
+        angle: f32,
+    ) -> bool {
+        self.pose(position, angle)
+            .into_iter()
+            .all(|(x, y)| match grid.locate(x, y) {
+                Location::Inside((x, y)) => !blocks(grid, elements, x, y, self.mass),
+                Location::Wall => false,
+                Location::Void => true,
+            })
+    }
+
+    /// Cells covered by the body with its pivot at `position`, turned by `angle`, before
+    /// wrapping around any edge.
+    fn pose(&self, position: (f32, f32), angle: f32) -> Vec<(isize, isize)> {
+        let pivot = (position.0.round() as isize, position.1.round() as isize);
+        self.shape
+            .iter()
+            .map(|&local| {
+                let (dx, dy) = rotate(local, angle);
+                (pivot.0 + dx, pivot.1 + dy)
+            })
+            .collect()
+    }
+}
+
+/// Whether the particle at `(x, y)` stops a body of average `mass`: solids always do, liquids
+/// only if the body is light enough to float on them, gases never.
+fn blocks(grid: &Grid, elements: &ElementRegistry, x: usize, y: usize, mass: f32) -> bool {
+    grid.get(x, y).is_some_and(|cell| {
+        let element = elements.get(cell.element);
+        match element.element_type {
+            ElementType::Gas => false,
+            ElementType::Liquid => element.mass > mass,
+            _ => true,
+        }
+    })
+}
+
+/// Whether anything under the group of cells at `positions` keeps it from falling.
+fn is_supported(
+    grid: &Grid,
+    elements: &ElementRegistry,
+    positions: &[(usize, usize)],
+    mass: f32,
+) -> bool {
+    let own: HashSet<(usize, usize)> = positions.iter().copied().collect();
+    positions
+        .iter()
+        .any(|&position| is_held_up(grid, elements, &own, position, mass))
+}
+
+/// Whether the cell at `(x, y)` of a group covering `own` sits on a solid bottom edge or on
+/// something that stops a body of average `mass`.
+fn is_held_up(
+    grid: &Grid,
+    elements: &ElementRegistry,
+    own: &HashSet<(usize, usize)>,
+    (x, y): (usize, usize),
+    mass: f32,
+) -> bool {
+    match grid.locate(x as isize, y as isize - 1) {
+        Location::Inside(below) => {
+            !own.contains(&below) && blocks(grid, elements, below.0, below.1, mass)
+        }
+        Location::Wall => true,
+        Location::Void => false,
+    }
+}
+
+/// Whether a group of cells reaches across a wrapping edge, touching both sides of it.
+fn straddles_seam(grid: &Grid, positions: &[(usize, usize)]) -> bool {
+    let spans = |axis: fn(&(usize, usize)) -> usize, len: usize| {
+        positions.iter().any(|p| axis(p) == 0) && positions.iter().any(|p| axis(p) + 1 == len)
+    };
+    let boundaries = grid.boundaries();
+    (boundaries.wraps_horizontally() && spans(|&(x, _)| x, grid.width()))
+        || (boundaries.wraps_vertically() && spans(|&(_, y)| y, grid.height()))
+}
+
+fn average_mass(elements: &ElementRegistry, cells: &[Cell]) -> f32 {
+    let total: f32 = cells
+        .iter()
+        .map(|cell| elements.get(cell.element).mass)
+        .sum();
+    total / cells.len().max(1) as f32
+}
+
+/// Rotates a cell offset anticlockwise by `angle`. Whole quarter turns are exact and the rest
+/// is done with three shears, each of which maps whole cells one to one, so a turned body never
+/// loses or doubles up cells.
+fn rotate((x, y): (isize, isize), angle: f32) -> (isize, isize) {
+    let quarters = (angle / FRAC_PI_2).round();
+    let rest = angle - quarters * FRAC_PI_2;
+    let (mut x, mut y) = match (quarters as i32).rem_euclid(4) {
+        0 => (x, y),
+        1 => (-y, x),
+        2 => (-x, -y),
+        _ => (y, -x),
+    };
+    let shear = -(rest / 2.0).tan();
+    let lift = rest.sin();
+    x += (shear * y as f32).round() as isize;
+    y += (lift * x as f32).round() as isize;
+    x += (shear * y as f32).round() as isize;
+    (x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::simulation::{Cell, Simulation};
+
+    fn count(simulation: &Simulation, name: &str) -> usize {
+        let id = simulation.elements().id(name).unwrap();
+        let grid = simulation.grid();
+        (0..grid.height())
+            .flat_map(|y| (0..grid.width()).map(move |x| (x, y)))
+            .filter(|&(x, y)| grid.get(x, y).is_some_and(|cell| cell.element == id))
+            .count()
+    }
+
+    fn place(
+        simulation: &mut Simulation,
+        name: &str,
+        xs: std::ops::Range<usize>,
+        ys: std::ops::Range<usize>,
+    ) {
+        let id = simulation.elements().id(name).unwrap();
+        for y in ys {
+            for x in xs.clone() {
+                let cell = Cell::new(simulation.elements(), id, [255; 4]);
+                simulation.set_cell(x, y, Some(cell));
+            }
+        }
+    }
+
+    #[test]
+    fn sinking_body_keeps_the_fluid_it_displaces() {
+        let mut simulation = Simulation::new(32, 64, 1);
+        place(&mut simulation, "Water", 0..32, 0..20);
+        // As wide as the world and taller than fluid is pushed up, so the water has nowhere to
+        // go but the cells the body leaves
+        place(&mut simulation, "Stone", 0..32, 24..36);
+        let water = count(&simulation, "Water");
+
+        for _ in 0..200 {
+            simulation.step();
+        }
+        assert_eq!(count(&simulation, "Water"), water);
+    }
+}
@@ -0,0 +1,18 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+/// Random number generator driving the simulation. ChaCha8 produces the same stream on every
+/// platform and `rand` version, so a seed is all that is needed to reproduce a run.
+pub type SimRng = ChaCha8Rng;
+
+pub fn seeded_rng(seed: u64) -> SimRng {
+    SimRng::seed_from_u64(seed)
+}
+
+/// RNG for one chunk during one tick. Every chunk gets its own stream, so the outcome does not
+/// depend on which thread updates which chunk or in what order.
+pub fn chunk_rng(seed: u64, tick: u64, chunk: usize) -> SimRng {
+    let mut rng = SimRng::seed_from_u64(seed ^ tick.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    rng.set_stream(chunk as u64);
+    rng
+}
@@ -0,0 +1,543 @@
+use crate::components::element_registry::{ElementId, ElementRegistry};
+use crate::simulation::{
+    cell::{Cell, Flow, Lifetime, Velocity},
+    chunk::Rect,
+    grid::Grid,
+};
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::PathBuf;
+
+/// Position of a chunk in the unbounded world, in chunks from the world origin.
+pub type ChunkCoord = (i64, i64);
+
+const MAGIC: &[u8; 4] = b"SNDC";
+/// Version chunks are saved as. Version 1 did not record the settings of sources and sinks.
+const VERSION: u8 = 2;
+
+/// Element index saved for a source or sink without an element.
+const NO_ELEMENT: u16 = u16::MAX;
+
+/// Chunks that have left the part of the world being simulated, waiting to be loaded again.
+///
+/// Chunks are kept encoded, with their elements recorded by name, so they survive the element
+/// definitions being reloaded while they are away. A store with a directory writes each chunk
+/// to its own file there, which also lets a world be picked up again in a later session; one
+/// without keeps them in memory. A chunk that could not be written is kept in memory instead.
+/// A chunk that could not be read is kept as it was saved, and never saved over.
+#[derive(Default)]
+pub struct ChunkStore {
+    directory: Option<PathBuf>,
+    memory: HashMap<ChunkCoord, Vec<u8>>,
+    unreadable: HashSet<ChunkCoord>,
+}
+
+impl ChunkStore {
+    /// A store that keeps chunks in memory for as long as it lives.
+    pub fn in_memory() -> Self {
+        ChunkStore::default()
+    }
+
+    /// A store that saves chunks as files in `directory`, creating it when first needed.
+    pub fn on_disk(directory: PathBuf) -> Self {
+        ChunkStore {
+            directory: Some(directory),
+            ..ChunkStore::default()
+        }
+    }
+
+    /// Directory chunks are saved to, if any.
+    pub fn directory(&self) -> Option<&PathBuf> {
+        self.directory.as_ref()
+    }
+
+    /// Number of chunks held in memory.
+    pub fn len(&self) -> usize {
+        self.memory.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.memory.is_empty()
+    }
+
+    fn path(&self, (x, y): ChunkCoord) -> Option<PathBuf> {
+        self.directory
+            .as_ref()
+            .map(|directory| directory.join(format!("{x}_{y}.chunk")))
+    }
+
+    /// Drops whatever is saved for the chunk at `coord`.
+    fn forget(&mut self, coord: ChunkCoord) -> io::Result<()> {
+        self.memory.remove(&coord);
+        let Some(path) = self.path(coord) else {
+            return Ok(());
+        };
+        match std::fs::remove_file(path) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        }
+    }
+
+    fn save(&mut self, coord: ChunkCoord, bytes: Vec<u8>) -> io::Result<()> {
+        let Some(path) = self.path(coord) else {
+            self.memory.insert(coord, bytes);
+            return Ok(());
+        };
+        let written = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&path, &bytes));
+        match written {
+            Ok(()) => {
+                self.memory.remove(&coord);
+                Ok(())
+            }
+            Err(error) => {
+                self.memory.insert(coord, bytes);
+                Err(error)
+            }
+        }
+    }
+
+    fn load(&mut self, coord: ChunkCoord) -> io::Result<Option<Vec<u8>>> {
+        if let Some(bytes) = self.memory.remove(&coord) {
+            return Ok(Some(bytes));
+        }
+        let Some(path) = self.path(coord) else {
+            return Ok(None);
+        };
+        match std::fs::read(path) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Saves the cells of `grid` in `rect` as the chunk at `coord`. Nothing is kept for an
+    /// empty chunk, and nothing is saved over a chunk that could not be read.
+    pub(crate) fn save_chunk(
+        &mut self,
+        coord: ChunkCoord,
+        grid: &Grid,
+        elements: &ElementRegistry,
+        rect: Rect,
+    ) -> io::Result<()> {
+        if self.unreadable.contains(&coord) {
+            return Ok(());
+        }
+        let empty =
+            (rect.min_y..rect.max_y).all(|y| (rect.min_x..rect.max_x).all(|x| grid.is_empty(x, y)));
+        if empty {
+            return self.forget(coord);
+        }
+        self.save(coord, encode(grid, elements, rect))
+    }
+
+    /// Fills `rect` of `grid` with the chunk saved at `coord`, leaving it untouched if there is
+    /// none. A chunk that cannot be read, such as one holding elements that are no longer
+    /// defined, leaves `rect` empty rather than half filled, and stays saved as it was.
+    pub(crate) fn load_chunk(
+        &mut self,
+        coord: ChunkCoord,
+        grid: &mut Grid,
+        elements: &ElementRegistry,
+        rect: Rect,
+    ) -> io::Result<()> {
+        let decoded = match self.load(coord) {
+            Ok(Some(bytes)) => decode(&bytes, elements, rect).inspect_err(|_| {
+                self.memory.insert(coord, bytes);
+                self.unreadable.insert(coord);
+            }),
+            Ok(None) => return Ok(()),
+            Err(error) => Err(error),
+        };
+        let (mut cells, result) = match decoded {
+            Ok(cells) => (cells.into_iter(), Ok(())),
+            Err(error) => (Vec::new().into_iter(), Err(error)),
+        };
+        for y in rect.min_y..rect.max_y {
+            for x in rect.min_x..rect.max_x {
+                grid.set(x, y, cells.next().flatten());
+            }
+        }
+        result
+    }
+}
+
+/// Encodes the cells of `rect`. Rigid body membership is not kept; bodies are released before
+/// their chunks are saved.
+fn encode(grid: &Grid, elements: &ElementRegistry, rect: Rect) -> Vec<u8> {
+    let mut names: Vec<ElementId> = Vec::new();
+    let mut index_of = |id: ElementId| {
+        let index = names
+            .iter()
+            .position(|&name| name == id)
+            .unwrap_or_else(|| {
+                names.push(id);
+                names.len() - 1
+            });
+        index as u16
+    };
+    let mut cells = Vec::new();
+    for y in rect.min_y..rect.max_y {
+        for x in rect.min_x..rect.max_x {
+            let Some(cell) = grid.get(x, y) else {
+                cells.push(0);
+                continue;
+            };
+            cells.push(1);
+            cells.extend(index_of(cell.element).to_le_bytes());
+            cells.extend(cell.color);
+            cells.extend(cell.velocity.x.to_le_bytes());
+            cells.extend(cell.velocity.y.to_le_bytes());
+            cells.extend(cell.direction.to_le_bytes());
+            cells.extend(cell.temperature.to_le_bytes());
+            cells.extend(cell.pressure.to_le_bytes());
+            cells.extend(cell.stress.to_le_bytes());
+            match cell.lifetime {
+                Some(lifetime) => {
+                    cells.push(1);
+                    cells.extend(lifetime.left.to_le_bytes());
+                    cells.extend(lifetime.total.to_le_bytes());
+                }
+                None => cells.push(0),
+            }
+            match cell.burning {
+                Some(ticks) => {
+                    cells.push(1);
+                    cells.extend(ticks.to_le_bytes());
+                }
+                None => cells.push(0),
+            }
+            match cell.flow {
+                Some(flow) => {
+                    cells.push(1);
+                    let element = flow.element.map_or(NO_ELEMENT, &mut index_of);
+                    cells.extend(element.to_le_bytes());
+                    cells.extend(flow.rate.to_le_bytes());
+                }
+                None => cells.push(0),
+            }
+        }
+    }
+
+    let mut bytes = Vec::with_capacity(cells.len() + 64);
+    bytes.extend(MAGIC);
+    bytes.push(VERSION);
+    bytes.extend((names.len() as u16).to_le_bytes());
+    for id in names {
+        let name = elements.get(id).element.as_bytes();
+        bytes.extend((name.len() as u16).to_le_bytes());
+        bytes.extend(name);
+    }
+    bytes.extend(cells);
+    bytes
+}
+
+/// Decodes the cells of `rect`, row by row. Fails on anything a simulation could not take in,
+/// including elements missing from `elements` and values that are not numbers.
+fn decode(bytes: &[u8], elements: &ElementRegistry, rect: Rect) -> io::Result<Vec<Option<Cell>>> {
+    let mut reader = Reader(bytes);
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(invalid("not a saved chunk"));
+    }
+    let version = reader.u8()?;
+    if !(1..=VERSION).contains(&version) {
+        return Err(invalid("saved chunk is from a newer version"));
+    }
+    let ids = (0..reader.u16()?)
+        .map(|_| {
+            let len = reader.u16()? as usize;
+            let name = std::str::from_utf8(reader.take(len)?)
+                .map_err(|_| invalid("element name is not UTF-8"))?;
+            elements
+                .id(name)
+                .map_err(|_| invalid(&format!("saved chunk holds unknown element \"{name}\"")))
+        })
+        .collect::<io::Result<Vec<ElementId>>>()?;
+
+    let mut cells = Vec::with_capacity((rect.max_x - rect.min_x) * (rect.max_y - rect.min_y));
+    for _ in rect.min_y..rect.max_y {
+        for _ in rect.min_x..rect.max_x {
+            if reader.u8()? == 0 {
+                cells.push(None);
+                continue;
+            }
+            let index = reader.u16()? as usize;
+            let element = *ids
+                .get(index)
+                .ok_or_else(|| invalid("unknown element index"))?;
+            let color = reader.take(4)?.try_into().expect("took four bytes");
+            let velocity = Velocity {
+                x: reader.finite()?,
+                y: reader.finite()?,
+            };
+            let direction = reader.u8()? as i8;
+            let temperature = reader.finite()?;
+            let pressure = reader.finite()?;
+            let stress = reader.u16()?;
+            let lifetime = match reader.u8()? {
+                0 => None,
+                _ => {
+                    let (left, total) = (reader.u32()?, reader.u32()?);
+                    if left > total {
+                        return Err(invalid("lifetime left is longer than the whole lifetime"));
+                    }
+                    Some(Lifetime { left, total })
+                }
+            };
+            let burning = match reader.u8()? {
+                0 => None,
+                _ => Some(reader.u32()?),
+            };
+            let flow = match version {
+                1 => None,
+                _ => match reader.u8()? {
+                    0 => None,
+                    _ => {
+                        let element = match reader.u16()? {
+                            NO_ELEMENT => None,
+                            index => Some(
+                                *ids.get(index as usize)
+                                    .ok_or_else(|| invalid("unknown element index"))?,
+                            ),
+                        };
+                        let rate = reader.finite()?;
+                        if rate < 0.0 {
+                            return Err(invalid("flow rate is out of range"));
+                        }
+                        Some(Flow { element, rate })
+                    }
+                },
+            };
+            cells.push(Some(Cell {
+                velocity,
+                direction,
+                temperature,
+                pressure,
+                stress,
+                lifetime,
+                burning,
+                flow: Flow::of(elements.get(element)).map(|default| flow.unwrap_or(default)),
+                ..Cell::new(elements, element, color)
+            }));
+        }
+    }
+    Ok(cells)
+}
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason)
+}
+
+/// Reads little-endian values off the front of a byte slice.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(invalid("saved chunk is truncated"));
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_le_bytes(
+            self.take(2)?.try_into().expect("took two bytes"),
+        ))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(
+            self.take(4)?.try_into().expect("took four bytes"),
+        ))
+    }
+
+    /// Reads an `f32`, failing on one that is infinite or not a number.
+    fn finite(&mut self) -> io::Result<f32> {
+        let value = f32::from_le_bytes(self.take(4)?.try_into().expect("took four bytes"));
+        if !value.is_finite() {
+            return Err(invalid("saved chunk holds a value that is not a number"));
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::element_registry::ElementDefinitions;
+
+    const RECT: Rect = Rect {
+        min_x: 0,
+        min_y: 0,
+        max_x: 32,
+        max_y: 32,
+    };
+
+    fn cell(elements: &ElementRegistry, name: &str) -> Cell {
+        Cell::new(elements, elements.id(name).unwrap(), [10, 20, 30, 40])
+    }
+
+    /// A chunk with one particle of each kind of state a chunk saves.
+    fn grid(elements: &ElementRegistry) -> Grid {
+        let mut grid = Grid::new(32, 32);
+        grid.set(
+            3,
+            4,
+            Some(Cell {
+                velocity: Velocity { x: -1.5, y: 2.25 },
+                direction: -1,
+                temperature: 321.5,
+                pressure: 0.75,
+                stress: 7,
+                lifetime: Some(Lifetime { left: 5, total: 9 }),
+                burning: Some(12),
+                ..cell(elements, "Sand")
+            }),
+        );
+        grid.set(
+            31,
+            31,
+            Some(Cell {
+                flow: Some(Flow {
+                    element: Some(elements.id("Water").unwrap()),
+                    rate: 2.5,
+                }),
+                ..cell(elements, "Source")
+            }),
+        );
+        grid.set(0, 0, Some(cell(elements, "Sink")));
+        grid
+    }
+
+    #[test]
+    fn round_trip_keeps_every_field() {
+        let elements = ElementRegistry::default();
+        let grid = grid(&elements);
+        let cells = decode(&encode(&grid, &elements, RECT), &elements, RECT).unwrap();
+
+        for y in 0..32 {
+            for x in 0..32 {
+                let decoded = cells[y * 32 + x].as_ref();
+                let Some(saved) = grid.get(x, y) else {
+                    assert!(decoded.is_none(), "({x}, {y}) should be empty");
+                    continue;
+                };
+                let decoded = decoded.unwrap();
+                assert_eq!(decoded.element, saved.element);
+                assert_eq!(decoded.color, saved.color);
+                assert!(decoded.velocity == saved.velocity);
+                assert_eq!(decoded.direction, saved.direction);
+                assert_eq!(decoded.temperature, saved.temperature);
+                assert_eq!(decoded.pressure, saved.pressure);
+                assert_eq!(decoded.stress, saved.stress);
+                assert_eq!(
+                    decoded
+                        .lifetime
+                        .map(|lifetime| (lifetime.left, lifetime.total)),
+                    saved
+                        .lifetime
+                        .map(|lifetime| (lifetime.left, lifetime.total))
+                );
+                assert_eq!(decoded.burning, saved.burning);
+                assert_eq!(decoded.flow, saved.flow);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_bad_magic_and_unknown_version() {
+        let elements = ElementRegistry::default();
+        let bytes = encode(&grid(&elements), &elements, RECT);
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        assert!(decode(&bad_magic, &elements, RECT).is_err());
+
+        let mut newer = bytes.clone();
+        newer[MAGIC.len()] = VERSION + 1;
+        assert!(decode(&newer, &elements, RECT).is_err());
+    }
+
+    #[test]
+    fn rejects_values_out_of_range() {
+        let elements = ElementRegistry::default();
+        let invalid = [
+            Cell {
+                temperature: f32::NAN,
+                ..cell(&elements, "Sand")
+            },
+            Cell {
+                velocity: Velocity {
+                    x: 0.0,
+                    y: f32::NEG_INFINITY,
+                },
+                ..cell(&elements, "Sand")
+            },
+            Cell {
+                lifetime: Some(Lifetime { left: 10, total: 5 }),
+                ..cell(&elements, "Sand")
+            },
+            Cell {
+                flow: Some(Flow {
+                    element: None,
+                    rate: -1.0,
+                }),
+                ..cell(&elements, "Sink")
+            },
+        ];
+        for cell in invalid {
+            let mut grid = Grid::new(32, 32);
+            grid.set(5, 5, Some(cell));
+            assert!(decode(&encode(&grid, &elements, RECT), &elements, RECT).is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_elements_that_are_not_defined() {
+        let elements = ElementRegistry::default();
+        let bytes = encode(&grid(&elements), &elements, RECT);
+
+        let mut definitions = ElementDefinitions::default();
+        definitions
+            .elements
+            .retain(|element| element.element != "Sink");
+        let without_sink = ElementRegistry::new(&definitions).unwrap();
+        assert!(decode(&bytes, &without_sink, RECT).is_err());
+    }
+
+    #[test]
+    fn truncated_chunk_loads_empty_and_stays_saved() {
+        let elements = ElementRegistry::default();
+        let mut store = ChunkStore::in_memory();
+        store
+            .save_chunk((0, 0), &grid(&elements), &elements, RECT)
+            .unwrap();
+        let saved = store.memory[&(0, 0)].clone();
+        let truncated = saved[..saved.len() / 2].to_vec();
+        store.memory.insert((0, 0), truncated.clone());
+
+        // Filled with something else first, so a half loaded chunk would show
+        let mut target = Grid::new(32, 32);
+        for y in 0..32 {
+            for x in 0..32 {
+                target.set(x, y, Some(cell(&elements, "Stone")));
+            }
+        }
+        assert!(store
+            .load_chunk((0, 0), &mut target, &elements, RECT)
+            .is_err());
+        assert!((0..32).all(|y| (0..32).all(|x| target.is_empty(x, y))));
+
+        // Saving the empty chunk in its place must not lose what was saved
+        store.save_chunk((0, 0), &target, &elements, RECT).unwrap();
+        assert_eq!(store.memory[&(0, 0)], truncated);
+    }
+}
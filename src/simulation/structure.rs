@@ -0,0 +1,139 @@
+use crate::components::element::ElementType;
+use crate::components::element_registry::ElementRegistry;
+use crate::simulation::{boundary::Location, chunk::Rect, grid::Grid};
+
+/// Computes the stress every structural solid in `rect` will have after one tick, reading only
+/// the current stresses. Returns `(x, y, stress)` for each particle whose stress changes.
+///
+/// A particle resting on a solid bottom edge, or on anything that is neither a solid structure
+/// nor a gas, is fully supported. Otherwise its stress is the lowest it can get through a
+/// neighbouring static solid: that of the one below it, or one more than that of one beside or
+/// above it.
+/// Solids without a [`Structure`](crate::components::element::Structure) hold any load and so
+/// count as fully supported neighbours. Stress spreads one cell per tick, so a particle cut off
+/// from support keeps climbing until it passes its strength and crumbles.
+pub fn settle(grid: &Grid, elements: &ElementRegistry, rect: Rect) -> Vec<(usize, usize, u16)> {
+    let mut stresses = Vec::new();
+
+    // Stress a static solid at `(x, y)` lends its neighbours, or `None` if it holds nothing up
+    let support = |(x, y): (usize, usize)| {
+        let cell = grid.get(x, y)?;
+        let element = elements.get(cell.element);
+        if cell.body.is_some() || element.element_type != ElementType::ImmovableSolid {
+            return None;
+        }
+        Some(element.structure.as_ref().map_or(0, |_| cell.stress))
+    };
+
+    for y in rect.min_y..rect.max_y {
+        for x in rect.min_x..rect.max_x {
+            let Some(cell) = grid.get(x, y) else {
+                continue;
+            };
+            let element = elements.get(cell.element);
+            if cell.body.is_some()
+                || element.element_type != ElementType::ImmovableSolid
+                || element.structure.is_none()
+            {
+                continue;
+            }
+
+            let below = match grid.locate(x as isize, y as isize - 1) {
+                Location::Wall => Some(0),
+                Location::Void => None,
+                Location::Inside(under) => {
+                    let element = grid.get(under.0, under.1).map(|cell| cell.element);
+                    match element.map(|element| elements.get(element).element_type) {
+                        None | Some(ElementType::Gas) => None,
+                        Some(ElementType::ImmovableSolid) => support(under),
+                        Some(_) => Some(0),
+                    }
+                }
+            };
+            let stress = [(-1, 0), (1, 0), (0, 1)]
+                .into_iter()
+                .filter_map(|(dx, dy)| grid.neighbour((x, y), dx, dy))
+                .filter_map(support)
+                .map(|stress| stress.saturating_add(1))
+                .chain(below)
+                .min()
+                .unwrap_or(cell.stress.saturating_add(1));
+
+            if stress != cell.stress {
+                stresses.push((x, y, stress));
+            }
+        }
+    }
+    stresses
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::components::element_registry::{ElementDefinitions, ElementRegistry};
+    use crate::simulation::{Cell, Simulation};
+
+    fn stone(simulation: &mut Simulation, x: usize, y: usize) {
+        let id = simulation.elements().id("Stone").unwrap();
+        let cell = Cell::new(simulation.elements(), id, [255; 4]);
+        simulation.set_cell(x, y, Some(cell));
+    }
+
+    fn is(simulation: &Simulation, x: usize, y: usize, name: &str) -> bool {
+        simulation
+            .get_cell(x, y)
+            .is_some_and(|cell| simulation.elements().get(cell.element).element == name)
+    }
+
+    /// A pillar of stone at `x = 4` standing on the bottom edge, with a beam of `length` cells
+    /// sticking out to the right of its top and, if `bridged`, a second pillar holding up the
+    /// far end. Stone holds up to 8 cells of overhang.
+    fn beam(simulation: &mut Simulation, length: usize, bridged: bool) {
+        for y in 0..10 {
+            stone(simulation, 4, y);
+            if bridged {
+                stone(simulation, 5 + length, y);
+            }
+        }
+        for x in 5..5 + length {
+            stone(simulation, x, 9);
+        }
+        for _ in 0..100 {
+            simulation.step();
+        }
+    }
+
+    #[test]
+    fn supported_span_holds() {
+        let mut simulation = Simulation::new(32, 32, 1);
+        beam(&mut simulation, 14, true);
+        assert!((5..19).all(|x| is(&simulation, x, 9, "Stone")));
+    }
+
+    #[test]
+    fn overhang_past_its_strength_crumbles() {
+        let mut simulation = Simulation::new(32, 32, 1);
+        beam(&mut simulation, 12, false);
+        assert!((5..13).all(|x| is(&simulation, x, 9, "Stone")));
+        assert!((13..17).all(|x| !is(&simulation, x, 9, "Stone")));
+        let rubble = (0..32)
+            .flat_map(|y| (0..32).map(move |x| (x, y)))
+            .filter(|&(x, y)| is(&simulation, x, y, "Rubble"))
+            .count();
+        assert_eq!(rubble, 4);
+    }
+
+    #[test]
+    fn overhang_without_debris_disappears() {
+        let mut definitions = ElementDefinitions::default();
+        for element in &mut definitions.elements {
+            if let Some(structure) = &mut element.structure {
+                structure.crumbles_into = None;
+            }
+        }
+        let mut simulation = Simulation::new(32, 32, 1);
+        simulation.set_elements(ElementRegistry::new(&definitions).unwrap());
+        beam(&mut simulation, 12, false);
+        assert!((5..13).all(|x| is(&simulation, x, 9, "Stone")));
+        assert!((13..32).all(|x| (0..32).all(|y| simulation.get_cell(x, y).is_none())));
+    }
+}
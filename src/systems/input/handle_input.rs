@@ -1,78 +1,169 @@
-use crate::components::element::{Element, ElementType};
+use crate::components::element::ElementType;
 use crate::resources::{
+    debug_overlay::DebugOverlay, elements::Elements, flow_settings::FlowSettings,
     mouse_state::MouseState, particle_matrix::ParticleMatrix, placement_size::PlacementSize,
-    selected_element::SelectedElement,
+    selected_element::SelectedElement, simulation_clock::SimulationClock,
+    world_config::WorldConfig,
 };
-use crate::utils::constants::*;
+use crate::simulation::Flow;
+use crate::utils::constants::BRUSH_HEAT;
 use crate::utils::particles::spawn_particle;
 use bevy::input::keyboard::KeyboardInput;
 use bevy::prelude::*;
 
 pub fn handle_input(
-    mut commands: Commands,
     mouse_state: Res<MouseState>,
     mut keyboard_input: EventReader<KeyboardInput>,
     mut particle_matrix: ResMut<ParticleMatrix>,
+    elements: Res<Elements>,
     mut selected_particle: ResMut<SelectedElement>,
     mut placement_size: ResMut<PlacementSize>,
+    mut debug_overlay: ResMut<DebugOverlay>,
+    mut clock: ResMut<SimulationClock>,
+    mut flow_settings: ResMut<FlowSettings>,
+    config: Res<WorldConfig>,
 ) {
     // Update selected particle
     for event in keyboard_input.read() {
+        if let Some(name) = element_key(event.key_code) {
+            match elements.id(name) {
+                Ok(id) => {
+                    selected_particle.0 = id;
+                    // Sources create, and sinks delete, whichever particle was picked last
+                    if is_particle(elements.get(id).element_type) {
+                        flow_settings.element = name.to_string();
+                    }
+                }
+                Err(error) => error!("{error}"),
+            }
+            continue;
+        }
         match event.key_code {
-            KeyCode::Digit1 => selected_particle.0 = Element::new("Sand".to_string()),
-            KeyCode::Digit2 => selected_particle.0 = Element::new("Water".to_string()),
-            KeyCode::Digit3 => selected_particle.0 = Element::new("Smoke".to_string()),
-            KeyCode::Digit4 => selected_particle.0 = Element::new("Stone".to_string()),
-            KeyCode::Digit5 => selected_particle.0 = Element::new("Erase".to_string()),
             KeyCode::Minus => {
                 placement_size.size = (placement_size.size - 10.0).max(10.0);
             }
             KeyCode::Equal => {
                 placement_size.size = (placement_size.size + 10.0).min(100.0);
             }
+            KeyCode::F1 if event.state.is_pressed() => {
+                debug_overlay.chunks = !debug_overlay.chunks;
+            }
+            KeyCode::F2 if event.state.is_pressed() => {
+                debug_overlay.heat = !debug_overlay.heat;
+            }
+            KeyCode::Space if event.state.is_pressed() => clock.toggle_pause(),
+            KeyCode::Period if event.state.is_pressed() => clock.step(),
+            KeyCode::BracketLeft if event.state.is_pressed() => clock.slower(),
+            KeyCode::BracketRight if event.state.is_pressed() => clock.faster(),
+            KeyCode::Semicolon if event.state.is_pressed() => {
+                flow_settings.slower();
+                info!("Sources and sinks: {} per tick", flow_settings.rate);
+            }
+            KeyCode::Quote if event.state.is_pressed() => {
+                flow_settings.faster();
+                info!("Sources and sinks: {} per tick", flow_settings.rate);
+            }
+            KeyCode::Backslash if event.state.is_pressed() => {
+                flow_settings.sinks_take_anything = !flow_settings.sinks_take_anything;
+                if flow_settings.sinks_take_anything {
+                    info!("Sinks delete anything");
+                } else {
+                    info!("Sinks delete {}", flow_settings.element);
+                }
+            }
             _ => {}
         }
     }
 
     // Handle mouse input for particle placement or erasure
     if mouse_state.button_pressed {
-        let half_size = placement_size.size / 2.0;
-        let top_left = Vec2::new(
-            (placement_size.position.x - half_size).max(LEFT_WALL),
-            (placement_size.position.y + half_size).min(TOP_WALL),
+        let element_type = elements.get(selected_particle.0).element_type;
+        let flow = Flow {
+            element: match element_type {
+                ElementType::Sink if flow_settings.sinks_take_anything => None,
+                _ => elements.id(&flow_settings.element).ok(),
+            },
+            rate: flow_settings.rate,
+        };
+        let half_size = Vec2::splat(placement_size.size / 2.0);
+        let brush = config.cells_in(
+            placement_size.position - half_size,
+            placement_size.position + half_size,
         );
-        let bottom_right = Vec2::new(
-            (placement_size.position.x + half_size).min(RIGHT_WALL),
-            (placement_size.position.y - half_size).max(BOTTOM_WALL),
-        );
-
-        for y in (bottom_right.y as i32..=top_left.y as i32).step_by(CHUNK_SIZE as usize) {
-            for x in (top_left.x as i32..=bottom_right.x as i32).step_by(CHUNK_SIZE as usize) {
-                let matrix_x = ((x as f32 - LEFT_WALL) / CHUNK_SIZE) as usize;
-                let matrix_y = ((y as f32 - BOTTOM_WALL) / CHUNK_SIZE) as usize;
 
-                if matrix_y < MATRIX_HEIGHT && matrix_x < MATRIX_WIDTH {
-                    match selected_particle.0.element_type {
-                        ElementType::Erase => {
-                            if let Some(entity) = particle_matrix.matrix[matrix_y][matrix_x] {
-                                commands.entity(entity).despawn();
-                                particle_matrix.matrix[matrix_y][matrix_x] = None;
-                            }
-                        }
-                        _ => {
-                            if particle_matrix.matrix[matrix_y][matrix_x].is_none() {
-                                spawn_particle(
-                                    &mut commands,
-                                    &mut particle_matrix,
-                                    matrix_x,
-                                    matrix_y,
-                                    selected_particle.0.clone(),
-                                );
-                            }
-                        }
+        for (matrix_x, matrix_y) in brush {
+            match element_type {
+                ElementType::Erase => {
+                    particle_matrix
+                        .simulation
+                        .set_cell(matrix_x, matrix_y, None);
+                }
+                ElementType::Heat => {
+                    particle_matrix
+                        .simulation
+                        .add_heat(matrix_x, matrix_y, BRUSH_HEAT);
+                }
+                ElementType::Cool => {
+                    particle_matrix
+                        .simulation
+                        .add_heat(matrix_x, matrix_y, -BRUSH_HEAT);
+                }
+                _ => {
+                    if particle_matrix
+                        .simulation
+                        .get_cell(matrix_x, matrix_y)
+                        .is_none()
+                    {
+                        spawn_particle(
+                            &mut particle_matrix,
+                            matrix_x,
+                            matrix_y,
+                            selected_particle.0,
+                        );
+                        particle_matrix
+                            .simulation
+                            .set_flow(matrix_x, matrix_y, flow);
                     }
                 }
             }
         }
     }
 }
+
+/// Name of the element selected by `key`, if it selects one.
+fn element_key(key: KeyCode) -> Option<&'static str> {
+    let name = match key {
+        KeyCode::Digit1 => "Sand",
+        KeyCode::Digit2 => "Water",
+        KeyCode::Digit3 => "Smoke",
+        KeyCode::Digit4 => "Stone",
+        KeyCode::Digit5 => "Erase",
+        KeyCode::Digit6 => "Oil",
+        KeyCode::Digit7 => "Honey",
+        KeyCode::KeyA => "Acid",
+        KeyCode::KeyF => "Fire",
+        KeyCode::KeyW => "Wood",
+        KeyCode::KeyG => "Gunpowder",
+        KeyCode::KeyL => "Lava",
+        KeyCode::KeyI => "Ice",
+        KeyCode::KeyV => "Steam",
+        KeyCode::KeyH => "Heat",
+        KeyCode::KeyC => "Cool",
+        KeyCode::KeyO => "Source",
+        KeyCode::KeyD => "Sink",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Whether elements of `element_type` are ordinary particles rather than tools, sources or
+/// sinks.
+fn is_particle(element_type: ElementType) -> bool {
+    matches!(
+        element_type,
+        ElementType::Liquid
+            | ElementType::MovableSolid
+            | ElementType::ImmovableSolid
+            | ElementType::Gas
+    )
+}
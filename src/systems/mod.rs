@@ -1,3 +1,4 @@
 pub mod setup;
 pub mod input;
+pub mod render;
 pub mod update;
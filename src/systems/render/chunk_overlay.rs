@@ -0,0 +1,41 @@
+use crate::resources::{
+    debug_overlay::DebugOverlay, particle_matrix::ParticleMatrix, world_config::WorldConfig,
+};
+use crate::simulation::Rect;
+use bevy::prelude::*;
+
+const CHUNK_COLOR: Color = Color::srgb(0.0, 1.0, 0.0);
+const DIRTY_RECT_COLOR: Color = Color::srgb(1.0, 0.0, 0.0);
+
+/// Outlines every chunk that was simulated during the last tick, along with its dirty rectangle.
+pub fn chunk_overlay(
+    debug_overlay: Res<DebugOverlay>,
+    particle_matrix: Res<ParticleMatrix>,
+    config: Res<WorldConfig>,
+    mut gizmos: Gizmos,
+) {
+    if !debug_overlay.chunks {
+        return;
+    }
+
+    let chunks = particle_matrix.simulation.grid().chunks();
+    for row in 0..chunks.rows() {
+        for column in 0..chunks.columns() {
+            if let Some(dirty) = chunks.get(column, row).dirty {
+                draw_rect(
+                    &mut gizmos,
+                    &config,
+                    chunks.bounds(column, row),
+                    CHUNK_COLOR,
+                );
+                draw_rect(&mut gizmos, &config, dirty, DIRTY_RECT_COLOR);
+            }
+        }
+    }
+}
+
+fn draw_rect(gizmos: &mut Gizmos, config: &WorldConfig, rect: Rect, color: Color) {
+    let min = config.cell_position(rect.min_x, rect.min_y);
+    let max = config.cell_position(rect.max_x, rect.max_y);
+    gizmos.rect_2d((min + max) / 2.0, 0.0, max - min, color);
+}
@@ -0,0 +1,125 @@
+use crate::resources::{
+    debug_overlay::DebugOverlay,
+    grid_texture::{GridTexture, GridTextureUpload},
+    particle_matrix::ParticleMatrix,
+};
+use crate::simulation::{Cell, AMBIENT_TEMPERATURE};
+use bevy::prelude::*;
+use bevy::render::{
+    extract_resource::ExtractResourcePlugin,
+    render_asset::RenderAssets,
+    render_resource::{Extent3d, ImageCopyTexture, ImageDataLayout, Origin3d, TextureAspect},
+    renderer::RenderQueue,
+    texture::GpuImage,
+    Render, RenderApp, RenderSet,
+};
+
+const EMPTY_PIXEL: [u8; 4] = [0, 0, 0, 0];
+
+/// Streams changed grid rows into the [`GridTexture`] without re-uploading the whole image.
+pub struct GridTexturePlugin;
+
+impl Plugin for GridTexturePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GridTextureUpload>().add_plugins((
+            ExtractResourcePlugin::<GridTexture>::default(),
+            ExtractResourcePlugin::<GridTextureUpload>::default(),
+        ));
+
+        app.sub_app_mut(RenderApp).add_systems(
+            Render,
+            upload_grid_texture.in_set(RenderSet::PrepareResources),
+        );
+    }
+}
+
+/// Rewrites the pixels of every row the simulation touched since the last frame.
+pub fn grid_texture(
+    mut particle_matrix: ResMut<ParticleMatrix>,
+    mut upload: ResMut<GridTextureUpload>,
+    debug_overlay: Res<DebugOverlay>,
+) {
+    let simulation = &mut particle_matrix.simulation;
+    if debug_overlay.is_changed() {
+        simulation.redraw_all();
+    }
+    let pixel = if debug_overlay.heat {
+        heat_color
+    } else {
+        |cell: &Cell| cell.color
+    };
+    let dirty_rows = simulation.take_dirty_rows();
+    let grid = simulation.grid();
+
+    upload.rows.clear();
+    for rows in dirty_rows {
+        // Texture rows run top to bottom while grid rows run bottom to top
+        let mut pixels = Vec::with_capacity(rows.len() * grid.width() * 4);
+        for y in rows.clone().rev() {
+            for x in 0..grid.width() {
+                let color = grid.get(x, y).map_or(EMPTY_PIXEL, pixel);
+                pixels.extend_from_slice(&color);
+            }
+        }
+        upload
+            .rows
+            .push(((grid.height() - rows.end) as u32, pixels));
+    }
+}
+
+/// Colours a particle by temperature: blue below freezing, black at ambient, then red, yellow and
+/// white as it heats up.
+fn heat_color(cell: &Cell) -> [u8; 4] {
+    let temperature = cell.temperature;
+    let (r, g, b) = if temperature < AMBIENT_TEMPERATURE {
+        let cold = ((AMBIENT_TEMPERATURE - temperature) / 40.0).clamp(0.0, 1.0);
+        (0.0, 0.3 * cold, cold)
+    } else {
+        let hot = ((temperature - AMBIENT_TEMPERATURE) / 1000.0).clamp(0.0, 1.0);
+        (
+            (hot * 3.0).min(1.0),
+            (hot * 3.0 - 1.0).clamp(0.0, 1.0),
+            (hot * 3.0 - 2.0).clamp(0.0, 1.0),
+        )
+    };
+    [(r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8, 255]
+}
+
+fn upload_grid_texture(
+    texture: Option<Res<GridTexture>>,
+    upload: Res<GridTextureUpload>,
+    gpu_images: Res<RenderAssets<GpuImage>>,
+    render_queue: Res<RenderQueue>,
+) {
+    let Some(gpu_image) = texture.and_then(|texture| gpu_images.get(&texture.0)) else {
+        return;
+    };
+    let width = gpu_image.size.x;
+
+    for (first_row, pixels) in &upload.rows {
+        let row_count = pixels.len() as u32 / (width * 4);
+        render_queue.write_texture(
+            ImageCopyTexture {
+                texture: &gpu_image.texture,
+                mip_level: 0,
+                origin: Origin3d {
+                    x: 0,
+                    y: *first_row,
+                    z: 0,
+                },
+                aspect: TextureAspect::All,
+            },
+            pixels,
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(width * 4),
+                rows_per_image: None,
+            },
+            Extent3d {
+                width,
+                height: row_count,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+}
@@ -0,0 +1,7 @@
+pub mod chunk_overlay;
+pub mod grid_texture;
+pub mod tick_counter;
+
+pub use chunk_overlay::chunk_overlay;
+pub use grid_texture::{grid_texture, GridTexturePlugin};
+pub use tick_counter::tick_counter;
@@ -0,0 +1,20 @@
+use crate::components::tick_counter::TickCounter;
+use crate::resources::{particle_matrix::ParticleMatrix, simulation_clock::SimulationClock};
+use bevy::prelude::*;
+
+/// Shows the current tick, and whether the simulation is paused or how fast it runs.
+pub fn tick_counter(
+    particle_matrix: Res<ParticleMatrix>,
+    clock: Res<SimulationClock>,
+    mut texts: Query<&mut Text, With<TickCounter>>,
+) {
+    let state = if clock.is_paused() {
+        "paused".to_string()
+    } else {
+        format!("{}x", clock.speed())
+    };
+    let tick = particle_matrix.simulation.tick();
+    for mut text in &mut texts {
+        text.sections[0].value = format!("Tick {tick} ({state})");
+    }
+}
@@ -1,3 +1,4 @@
+use crate::components::tick_counter::TickCounter;
 use bevy::prelude::*;
 
 use iyes_perf_ui::prelude::*;
@@ -5,4 +6,14 @@ pub fn ui(mut commands: Commands) {
     // create a simple Perf UI with default settings
     // and all entries provided by the crate:
     commands.spawn(PerfUiCompleteBundle::default());
+
+    commands.spawn((
+        TextBundle::from_section("", TextStyle::default()).with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(8.0),
+            left: Val::Px(8.0),
+            ..default()
+        }),
+        TickCounter,
+    ));
 }
@@ -1,17 +1,79 @@
-use crate::components::element::Element;
-use crate::resources::{particle_matrix::ParticleMatrix, selected_element::SelectedElement};
+use crate::components::{element_registry::ElementRegistry, world_sprite::WorldSprite};
+use crate::resources::{
+    element_definitions::ElementDefinitionsHandle, elements::Elements, grid_texture::GridTexture,
+    particle_matrix::ParticleMatrix, selected_element::SelectedElement,
+    simulation_seed::SimulationSeed, world_boundaries::WorldBoundaries, world_config::WorldConfig,
+};
+use crate::simulation::{Boundaries, Boundary};
 use crate::utils::constants::*;
 use bevy::prelude::*;
+use bevy::render::{
+    render_asset::RenderAssetUsages,
+    render_resource::{Extent3d, TextureDimension, TextureFormat},
+    texture::ImageSampler,
+};
 
-pub fn world(mut commands: Commands) {
-    commands.insert_resource(ParticleMatrix::new());
-    commands.insert_resource(SelectedElement(Element::new("Sand".to_string())));
+pub fn world(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    asset_server: Res<AssetServer>,
+    seed: Res<SimulationSeed>,
+    boundaries: Res<WorldBoundaries>,
+    config: Res<WorldConfig>,
+) {
+    info!("Simulation seed: {}", seed.0);
+    if config.infinite {
+        info!(
+            "Infinite world, simulated {}x{} cells at a time",
+            config.width, config.height
+        );
+    } else {
+        info!("World size: {}x{} cells", config.width, config.height);
+    }
+    // The built-in definitions are used until the asset file has loaded, and a saved world is
+    // only loaded once it has, see `reload_elements`
+    let boundaries = boundaries.of(&config);
+    let elements = ElementRegistry::default();
+    let mut particle_matrix = ParticleMatrix::new(&config, seed.0, elements.clone());
+    particle_matrix.simulation.set_boundaries(boundaries);
+    let sand = elements.id("Sand");
+    commands.insert_resource(SelectedElement(
+        sand.expect("built-in element definitions include Sand"),
+    ));
+    commands.insert_resource(Elements(elements));
+    commands.insert_resource(particle_matrix);
+    commands.insert_resource(ElementDefinitionsHandle(
+        asset_server.load(ELEMENT_DEFINITIONS),
+    ));
 
-    // Spawn walls
-    spawn_wall(&mut commands, WallLocation::Left);
-    spawn_wall(&mut commands, WallLocation::Right);
-    spawn_wall(&mut commands, WallLocation::Bottom);
-    spawn_wall(&mut commands, WallLocation::Top);
+    spawn_world(&mut commands, &mut images, &config, boundaries);
+}
+
+/// Spawns the sprites of a world of the given size: the particle grid, and walls along its
+/// solid edges. An infinite world has no walls, as its edges move with the camera.
+pub fn spawn_world(
+    commands: &mut Commands,
+    images: &mut Assets<Image>,
+    config: &WorldConfig,
+    boundaries: Boundaries,
+) {
+    spawn_grid_texture(commands, images, config);
+    if config.infinite {
+        return;
+    }
+
+    // Spawn walls along the solid edges; particles pass through the others
+    let edges = [
+        (WallLocation::Left, boundaries.left),
+        (WallLocation::Right, boundaries.right),
+        (WallLocation::Bottom, boundaries.bottom),
+        (WallLocation::Top, boundaries.top),
+    ];
+    for (location, boundary) in edges {
+        if boundary == Boundary::Solid {
+            spawn_wall(commands, config, location);
+        }
+    }
 }
 
 enum WallLocation {
@@ -21,33 +83,67 @@ enum WallLocation {
     Top,
 }
 
-fn spawn_wall(commands: &mut Commands, location: WallLocation) {
+fn spawn_wall(commands: &mut Commands, config: &WorldConfig, location: WallLocation) {
+    let (min, max) = (config.min(), config.max());
     let (position, size) = match location {
         WallLocation::Left => (
-            Vec2::new(LEFT_WALL, 0.),
-            Vec2::new(WALL_THICKNESS, TOP_WALL - BOTTOM_WALL + WALL_THICKNESS),
+            Vec2::new(min.x, 0.),
+            Vec2::new(WALL_THICKNESS, max.y - min.y + WALL_THICKNESS),
         ),
         WallLocation::Right => (
-            Vec2::new(RIGHT_WALL, 0.),
-            Vec2::new(WALL_THICKNESS, TOP_WALL - BOTTOM_WALL + WALL_THICKNESS),
+            Vec2::new(max.x, 0.),
+            Vec2::new(WALL_THICKNESS, max.y - min.y + WALL_THICKNESS),
         ),
         WallLocation::Bottom => (
-            Vec2::new(0., BOTTOM_WALL),
-            Vec2::new(RIGHT_WALL - LEFT_WALL + WALL_THICKNESS, WALL_THICKNESS),
+            Vec2::new(0., min.y),
+            Vec2::new(max.x - min.x + WALL_THICKNESS, WALL_THICKNESS),
         ),
         WallLocation::Top => (
-            Vec2::new(0., TOP_WALL),
-            Vec2::new(RIGHT_WALL - LEFT_WALL + WALL_THICKNESS, WALL_THICKNESS),
+            Vec2::new(0., max.y),
+            Vec2::new(max.x - min.x + WALL_THICKNESS, WALL_THICKNESS),
         ),
     };
 
-    commands.spawn(SpriteBundle {
-        transform: Transform::from_translation(position.extend(0.0)),
-        sprite: Sprite {
-            color: WALL_COLOR,
-            custom_size: Some(size),
+    commands
+        .spawn(SpriteBundle {
+            transform: Transform::from_translation(position.extend(0.0)),
+            sprite: Sprite {
+                color: WALL_COLOR,
+                custom_size: Some(size),
+                ..default()
+            },
             ..default()
+        })
+        .insert(WorldSprite);
+}
+
+/// Spawns the single sprite the particle grid is drawn on, one texel per cell.
+fn spawn_grid_texture(commands: &mut Commands, images: &mut Assets<Image>, config: &WorldConfig) {
+    // Only the GPU copy is kept; changed rows are written to it directly every frame
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: config.width as u32,
+            height: config.height as u32,
+            depth_or_array_layers: 1,
         },
-        ..default()
-    });
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.sampler = ImageSampler::nearest();
+    let handle = images.add(image);
+
+    commands
+        .spawn(SpriteBundle {
+            transform: Transform::from_xyz(0.0, 0.0, 1.0),
+            sprite: Sprite {
+                custom_size: Some(config.size()),
+                ..default()
+            },
+            texture: handle.clone(),
+            ..default()
+        })
+        .insert(WorldSprite);
+    commands.insert_resource(GridTexture(handle));
 }
@@ -0,0 +1,85 @@
+use crate::components::element_registry::{
+    ElementDefinitions, ElementError, ElementId, ElementRegistry,
+};
+use crate::resources::{
+    element_definitions::ElementDefinitionsAsset, elements::Elements,
+    particle_matrix::ParticleMatrix, selected_element::SelectedElement,
+};
+use bevy::asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+
+/// Loads element definitions from `.ron` asset files.
+pub struct ElementDefinitionsPlugin;
+
+impl Plugin for ElementDefinitionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<ElementDefinitionsAsset>()
+            .register_asset_loader(ElementDefinitionsLoader);
+    }
+}
+
+struct ElementDefinitionsLoader;
+
+impl AssetLoader for ElementDefinitionsLoader {
+    type Asset = ElementDefinitionsAsset;
+    type Settings = ();
+    type Error = ElementError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<ElementDefinitionsAsset, ElementError> {
+        let mut source = String::new();
+        reader.read_to_string(&mut source).await?;
+        ElementDefinitions::from_ron(&source).map(ElementDefinitionsAsset)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ron"]
+    }
+}
+
+/// Hands element definitions to the simulation whenever the file is loaded or edited. A file
+/// that fails to load is reported by the asset server and the previous definitions stay in use.
+pub fn reload_elements(
+    mut events: EventReader<AssetEvent<ElementDefinitionsAsset>>,
+    definitions: Res<Assets<ElementDefinitionsAsset>>,
+    mut registry: ResMut<Elements>,
+    mut particle_matrix: ResMut<ParticleMatrix>,
+    mut selected_element: ResMut<SelectedElement>,
+) {
+    for event in events.read() {
+        let (AssetEvent::Added { id } | AssetEvent::Modified { id }) = event else {
+            continue;
+        };
+        let Some(ElementDefinitionsAsset(definitions)) = definitions.get(*id) else {
+            continue;
+        };
+
+        let elements = match ElementRegistry::new(definitions) {
+            Ok(elements) => elements,
+            Err(error) => {
+                error!("{error}");
+                continue;
+            }
+        };
+        // IDs may be reassigned, so the selection follows the element's name
+        let selected = &registry.get(selected_element.0).element;
+        match elements.id(selected) {
+            Ok(id) => selected_element.0 = id,
+            Err(error) => {
+                warn!("{error}, selecting the first element instead");
+                selected_element.0 = ElementId(0);
+            }
+        }
+        registry.0 = elements.clone();
+        particle_matrix.simulation.set_elements(elements);
+        // A saved world waits for the definitions it was saved with before it is loaded
+        if let Err(error) = particle_matrix.load() {
+            error!("Could not load the saved world: {error}");
+        }
+        info!("Loaded {} element definitions", definitions.elements.len());
+    }
+}
@@ -1,7 +1,13 @@
+pub mod element_definitions;
 pub mod mouse_state;
+pub mod new_world;
 pub mod particles;
 pub mod placement_shape;
+pub mod stream_world;
 
+pub use element_definitions::{reload_elements, ElementDefinitionsPlugin};
 pub use mouse_state::mouse_state;
-pub use particles::particles;
+pub use new_world::new_world;
+pub use particles::{particles, simulation_speed};
 pub use placement_shape::placement_shape;
+pub use stream_world::{save_world, stream_world};
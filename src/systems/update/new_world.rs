@@ -0,0 +1,53 @@
+use crate::components::world_sprite::WorldSprite;
+use crate::resources::{
+    particle_matrix::ParticleMatrix, world_boundaries::WorldBoundaries, world_config::WorldConfig,
+};
+use crate::systems::setup::world::spawn_world;
+use crate::utils::camera::RotatingCamera;
+use bevy::prelude::*;
+
+/// Replaces the world with an empty one when N is pressed. The world configuration is read
+/// again first, so a size changed in the config file takes effect. The seed, boundaries and
+/// element definitions carry over. An infinite world saved to a directory is saved and then
+/// picked up again from there, as the saved world is what that directory holds.
+pub fn new_world(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut images: ResMut<Assets<Image>>,
+    mut config: ResMut<WorldConfig>,
+    boundaries: Res<WorldBoundaries>,
+    mut particle_matrix: ResMut<ParticleMatrix>,
+    sprites: Query<Entity, With<WorldSprite>>,
+    mut camera_query: Query<&mut Transform, With<RotatingCamera>>,
+) {
+    if !keys.just_pressed(KeyCode::KeyN) {
+        return;
+    }
+    if let Err(error) = particle_matrix.save() {
+        error!("Could not save the world: {error}");
+    }
+    match WorldConfig::from_args() {
+        Ok(new_config) => *config = new_config,
+        Err(error) => error!("{error}, keeping the current size"),
+    }
+
+    for entity in &sprites {
+        commands.entity(entity).despawn();
+    }
+    let old = &particle_matrix.simulation;
+    let (seed, elements) = (old.seed(), old.elements().clone());
+    let boundaries = boundaries.of(&config);
+    *particle_matrix = ParticleMatrix::new(&config, seed, elements);
+    particle_matrix.simulation.set_boundaries(boundaries);
+    if let Err(error) = particle_matrix.load() {
+        error!("Could not load the saved world: {error}");
+    }
+    spawn_world(&mut commands, &mut images, &config, boundaries);
+
+    // Start over looking at the middle of the new world
+    let mut camera_transform = camera_query.single_mut();
+    camera_transform.translation.x = 0.0;
+    camera_transform.translation.y = 0.0;
+    camera_transform.scale = Vec3::ONE;
+    info!("New world: {}x{} cells", config.width, config.height);
+}
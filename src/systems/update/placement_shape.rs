@@ -1,7 +1,6 @@
 use crate::components::{element::ElementType, placement_shape::PlacementShape};
 use crate::resources::*;
 use crate::utils::camera::RotatingCamera;
-use crate::utils::constants::*;
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 
@@ -12,6 +11,8 @@ pub fn placement_shape(
     mut placement_size: ResMut<PlacementSize>,
     mut placement_shape_query: Query<(Entity, &mut Transform, &mut Sprite), With<PlacementShape>>,
     selected_particle: Res<SelectedElement>,
+    elements: Res<Elements>,
+    config: Res<WorldConfig>,
 ) {
     let window = window_query.single();
     let (camera, camera_transform) = camera_query.single();
@@ -22,28 +23,29 @@ pub fn placement_shape(
         .map(|ray| ray.origin.truncate())
     {
         // Check if the mouse position is within bounds
-        if world_position.x >= LEFT_WALL
-            && world_position.x <= RIGHT_WALL
-            && world_position.y >= BOTTOM_WALL
-            && world_position.y <= TOP_WALL
-        {
+        if config.contains(world_position) {
             placement_size.position = world_position;
 
             let half_size = placement_size.size / 2.0;
             let top_left = Vec2::new(
-                (world_position.x - half_size).max(LEFT_WALL),
-                (world_position.y + half_size).min(TOP_WALL),
+                (world_position.x - half_size).max(config.min().x),
+                (world_position.y + half_size).min(config.max().y),
             );
             let bottom_right = Vec2::new(
-                (world_position.x + half_size).min(RIGHT_WALL),
-                (world_position.y - half_size).max(BOTTOM_WALL),
+                (world_position.x + half_size).min(config.max().x),
+                (world_position.y - half_size).max(config.min().y),
             );
             let size = bottom_right - top_left;
             let center = (top_left + bottom_right) / 2.0;
 
             // Determine the color based on the selected particle
-            let color = match selected_particle.0.element_type {
+            let selected = elements.get(selected_particle.0);
+            let color = match selected.element_type {
                 ElementType::Erase => Color::srgba(1.0, 0.0, 0.0, 0.2), // Semi-transparent red for Erase
+                ElementType::Heat | ElementType::Cool => {
+                    let color = selected.color;
+                    Color::srgba(color.r, color.g, color.b, 0.2)
+                }
                 _ => Color::srgba(1.0, 1.0, 1.0, 0.2), // Default color for other particles
             };
 
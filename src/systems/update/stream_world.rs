@@ -0,0 +1,37 @@
+use crate::resources::{particle_matrix::ParticleMatrix, world_config::WorldConfig};
+use crate::utils::camera::RotatingCamera;
+use bevy::prelude::*;
+
+/// Keeps the simulated window of an infinite world under the camera. Once the camera is a
+/// chunk or more away from the middle of the window, the window moves that many chunks along
+/// and the camera moves back by the same distance, so the view does not jump and the camera
+/// can pan forever without ever straying far from the origin.
+pub fn stream_world(
+    config: Res<WorldConfig>,
+    mut particle_matrix: ResMut<ParticleMatrix>,
+    mut camera_query: Query<&mut Transform, With<RotatingCamera>>,
+) {
+    if !particle_matrix.is_infinite() || !particle_matrix.is_loaded() {
+        return;
+    }
+    let mut camera_transform = camera_query.single_mut();
+    let chunks = (camera_transform.translation.truncate() / config.chunk_size()).trunc();
+    if chunks == Vec2::ZERO {
+        return;
+    }
+
+    if let Err(error) = particle_matrix.shift(chunks.x as i64, chunks.y as i64) {
+        error!("Could not save or load part of the world: {error}");
+    }
+    camera_transform.translation -= (chunks * config.chunk_size()).extend(0.0);
+}
+
+/// Saves an infinite world to its directory when the app exits.
+pub fn save_world(mut exits: EventReader<AppExit>, mut particle_matrix: ResMut<ParticleMatrix>) {
+    if exits.read().last().is_none() {
+        return;
+    }
+    if let Err(error) = particle_matrix.save() {
+        error!("Could not save the world: {error}");
+    }
+}
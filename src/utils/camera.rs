@@ -1,3 +1,4 @@
+use crate::resources::world_config::WorldConfig;
 use bevy::input::mouse::MouseWheel;
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
@@ -15,6 +16,7 @@ pub fn edge_scrolling(
     mut camera_query: Query<&mut Transform, With<RotatingCamera>>,
     window_query: Query<&Window, With<PrimaryWindow>>,
     time: Res<Time>,
+    config: Res<WorldConfig>,
 ) {
     let window = window_query.single();
     let mut camera_transform = camera_query.single_mut();
@@ -42,7 +44,12 @@ pub fn edge_scrolling(
 
         if move_direction != Vec2::ZERO {
             let move_amount = move_direction.normalize() * CAMERA_MOVE_SPEED * time.delta_seconds();
-            camera_transform.translation += move_amount.extend(0.0);
+            let mut position = camera_transform.translation.truncate() + move_amount;
+            // Keep the centre of the view over a bounded world; an infinite one follows it
+            if !config.infinite {
+                position = position.clamp(config.min(), config.max());
+            }
+            camera_transform.translation = position.extend(camera_transform.translation.z);
         }
     }
 }
@@ -1,11 +1,18 @@
 use bevy::prelude::*;
 // Constants
 pub const WALL_THICKNESS: f32 = 1.0;
-pub const LEFT_WALL: f32 = -3600.;
-pub const RIGHT_WALL: f32 = 3600.;
-pub const BOTTOM_WALL: f32 = -2400.;
-pub const TOP_WALL: f32 = 2400.;
 pub const WALL_COLOR: Color = Color::srgb(0.0, 0.0, 0.0);
-pub const CHUNK_SIZE: f32 = 10.0;
-pub const MATRIX_WIDTH: usize = ((RIGHT_WALL - LEFT_WALL) / CHUNK_SIZE) as usize;
-pub const MATRIX_HEIGHT: usize = ((TOP_WALL - BOTTOM_WALL) / CHUNK_SIZE) as usize;
+// Size of the world, in cells, and of each cell, in pixels, unless configured otherwise
+pub const DEFAULT_WORLD_WIDTH: usize = 720;
+pub const DEFAULT_WORLD_HEIGHT: usize = 480;
+pub const DEFAULT_CELL_SIZE: f32 = 10.0;
+// Largest width and height of the world, in cells, as the grid is drawn on a single texture and
+// GPUs are only required to support textures this large
+pub const MAX_WORLD_SIZE: usize = 8192;
+// Simulation ticks per second at normal speed
+pub const TICK_RATE: f64 = 60.0;
+// Degrees added to or removed from each cell under the brush per frame by the Heat and Cool tools
+pub const BRUSH_HEAT: f32 = 50.0;
+
+// Asset file the element definitions are loaded from
+pub const ELEMENT_DEFINITIONS: &str = "elements.ron";
@@ -1,10 +1,13 @@
-pub mod helper;
 pub mod similate_gas;
 pub mod similate_liquid;
 pub mod similate_movable_solid;
+pub mod similate_sink;
+pub mod similate_source;
 pub mod spawn_particle;
 
-pub use similate_gas::simulate_gas;
-pub use similate_liquid::simulate_liquid;
-pub use similate_movable_solid::simulate_movable_solid;
+pub use similate_gas::Gas;
+pub use similate_liquid::Liquid;
+pub use similate_movable_solid::MovableSolid;
+pub use similate_sink::Sink;
+pub use similate_source::Source;
 pub use spawn_particle::spawn_particle;
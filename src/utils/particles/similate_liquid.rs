@@ -1,43 +1,54 @@
-use crate::components::element::Element;
-use crate::resources::particle_matrix::ParticleMatrix;
-use crate::utils::particles::helper::*;
+use crate::simulation::{ParticleBehavior, ParticleContext, MAX_REACH};
+use crate::utils::particles::similate_movable_solid::{fall, slide};
 use rand::Rng;
 
-pub fn simulate_liquid(
-    x: usize,
-    y: usize,
-    particle_matrix: &ParticleMatrix,
-    rng: &mut impl Rng,
-    element: &Element,
-) -> (usize, usize) {
-    let x = x as isize;
-    let y = y as isize;
+/// Built-in behaviour of liquids: falls and slides like a movable solid, then flows sideways
+/// by up to its dispersion rate in cells per tick, keeping the same direction until it is
+/// blocked.
+pub struct Liquid;
 
-    if is_in_bounds(x, y - 1) && is_empty(particle_matrix, x as usize, (y - 1) as usize) {
-        (x as usize, (y - 1) as usize)
-    } else {
-        let left =
-            is_in_bounds(x - 1, y) && is_empty(particle_matrix, (x - 1) as usize, y as usize);
-        let right =
-            is_in_bounds(x + 1, y) && is_empty(particle_matrix, (x + 1) as usize, y as usize);
+impl ParticleBehavior for Liquid {
+    fn update(&self, ctx: &mut ParticleContext) {
+        if fall(ctx) || slide(ctx) {
+            return;
+        }
+
+        // Fractional rates give a chance of flowing one cell further
+        let dispersion_rate = ctx.element().dispersion_rate.clamp(0.0, MAX_REACH as f32);
+        let extra = ctx.rng().gen_bool(dispersion_rate.fract() as f64);
+        let reach = dispersion_rate as isize + extra as isize;
+        if reach == 0 {
+            return;
+        }
 
-        if left && right {
-            if rng.gen_bool(0.5) {
-                ((x - 1) as usize, y as usize)
-            } else {
-                ((x + 1) as usize, y as usize)
+        let direction = match ctx.direction() {
+            0 if ctx.rng().gen_bool(0.5) => -1,
+            0 => 1,
+            direction => direction.signum(),
+        };
+        for direction in [direction, -direction] {
+            let distance = flow(ctx, direction as isize, reach);
+            if distance > 0 {
+                ctx.move_by(direction as isize * distance, 0);
+                ctx.set_direction(direction);
+                return;
             }
-        } else if left {
-            ((x - 1) as usize, y as usize)
-        } else if right {
-            ((x + 1) as usize, y as usize)
-        } else if is_in_bounds(x, y + 1)
-            && is_empty(particle_matrix, x as usize, (y + 1) as usize)
-            && rng.gen_bool(element.dispersion_rate as f64 / 100.0)
-        {
-            (x as usize, (y + 1) as usize) // Chance to move up (bubbling effect) based on dispersion rate
-        } else {
-            (x as usize, y as usize)
         }
     }
 }
+
+/// How many cells the particle can flow in `direction`, at most `reach`. It stops early over
+/// an empty cell so that it drops into gaps instead of flowing past them.
+fn flow(ctx: &ParticleContext, direction: isize, reach: isize) -> isize {
+    let mut distance = 0;
+    for step in 1..=reach {
+        if !ctx.is_empty(direction * step, 0) {
+            break;
+        }
+        distance = step;
+        if ctx.is_empty(direction * step, -1) {
+            break;
+        }
+    }
+    distance
+}
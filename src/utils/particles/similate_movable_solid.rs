@@ -1,38 +1,68 @@
-use crate::components::element::Element;
-use crate::resources::particle_matrix::ParticleMatrix;
-use crate::utils::particles::helper::*;
+use crate::simulation::{ParticleBehavior, ParticleContext};
 use rand::Rng;
 
-pub fn simulate_movable_solid(
-    x: usize,
-    y: usize,
-    particle_matrix: &ParticleMatrix,
-    rng: &mut impl Rng,
-    element: &Element,
-) -> (usize, usize) {
-    let x = x as isize;
-    let y = y as isize;
+/// Built-in behaviour of movable solids: falls, slides to a stop, then tumbles down
+/// diagonally.
+pub struct MovableSolid;
 
-    if is_in_bounds(x, y - 1) && is_empty(particle_matrix, x as usize, (y - 1) as usize) {
-        (x as usize, (y - 1) as usize)
-    } else {
-        let down_left = is_in_bounds(x - 1, y - 1)
-            && is_empty(particle_matrix, (x - 1) as usize, (y - 1) as usize);
-        let down_right = is_in_bounds(x + 1, y - 1)
-            && is_empty(particle_matrix, (x + 1) as usize, (y - 1) as usize);
+impl ParticleBehavior for MovableSolid {
+    fn update(&self, ctx: &mut ParticleContext) {
+        if fall(ctx) || slide(ctx) {
+            return;
+        }
+
+        let friction = ctx.element().friction;
+        let down_left = ctx.is_empty(-1, -1);
+        let down_right = ctx.is_empty(1, -1);
 
-        if down_left && down_right {
-            if rng.gen_bool(0.5 - element.friction as f64 / 2.0) {
-                ((x - 1) as usize, (y - 1) as usize)
+        let dx = if down_left && down_right {
+            if ctx.rng().gen_bool(0.5 - friction as f64 / 2.0) {
+                -1
             } else {
-                ((x + 1) as usize, (y - 1) as usize)
+                1
             }
         } else if down_left {
-            ((x - 1) as usize, (y - 1) as usize)
+            -1
         } else if down_right {
-            ((x + 1) as usize, (y - 1) as usize)
+            1
         } else {
-            (x as usize, y as usize)
-        }
+            return;
+        };
+        ctx.move_by(dx, -1);
+    }
+}
+
+/// Accelerates a particle with nothing below it and moves it as far as its velocity takes it
+/// this tick. Returns `false` if it is resting on something, turning any fall speed it had into
+/// a sideways scatter.
+pub fn fall(ctx: &mut ParticleContext) -> bool {
+    let mut velocity = ctx.velocity();
+    if !ctx.is_empty(0, -1) {
+        let friction = ctx.element().friction;
+        velocity.land(ctx.rng(), friction);
+        ctx.set_velocity(velocity);
+        return false;
+    }
+    velocity.accelerate();
+    ctx.set_velocity(velocity);
+    let (dx, dy) = velocity.offset();
+    ctx.move_by(dx, dy);
+    true
+}
+
+/// Carries a grounded particle sideways while it still has horizontal speed, losing speed to
+/// friction. Returns `false` once it has stopped or is blocked.
+pub fn slide(ctx: &mut ParticleContext) -> bool {
+    let mut velocity = ctx.velocity();
+    let (dx, _) = velocity.offset();
+    let (reached, _) = ctx.trace(dx, 0);
+    velocity.brake(ctx.element().friction);
+    if reached == 0 {
+        velocity.x = 0.0;
+        ctx.set_velocity(velocity);
+        return false;
     }
+    ctx.set_velocity(velocity);
+    ctx.move_by(reached, 0);
+    true
 }
@@ -0,0 +1,34 @@
+use crate::components::element::ElementType;
+use crate::simulation::{ParticleBehavior, ParticleContext};
+use rand::seq::SliceRandom;
+
+/// Built-in behaviour of sinks: deletes the particles touching them, up to their configured
+/// rate per tick. Other sources and sinks are left alone.
+pub struct Sink;
+
+impl ParticleBehavior for Sink {
+    fn update(&self, ctx: &mut ParticleContext) {
+        let flow = ctx.cell().flow.unwrap_or_default();
+        let mut touching: Vec<(isize, isize)> = [(0, -1), (-1, 0), (1, 0), (0, 1)]
+            .into_iter()
+            .filter(|&(dx, dy)| {
+                ctx.get(dx, dy).is_some_and(|cell| {
+                    let element_type = ctx.elements().get(cell.element).element_type;
+                    !matches!(element_type, ElementType::Source | ElementType::Sink)
+                        && flow.element.is_none_or(|element| element == cell.element)
+                })
+            })
+            .collect();
+        if touching.is_empty() {
+            return;
+        }
+        // Stay awake while anything is left to drain
+        ctx.stay_awake();
+
+        touching.shuffle(ctx.rng());
+        let count = flow.roll(ctx.rng());
+        for (dx, dy) in touching.into_iter().take(count) {
+            ctx.remove(dx, dy);
+        }
+    }
+}
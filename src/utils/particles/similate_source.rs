@@ -0,0 +1,36 @@
+use crate::components::element::ElementType;
+use crate::simulation::{ParticleBehavior, ParticleContext};
+use rand::seq::SliceRandom;
+
+/// Built-in behaviour of sources: creates particles of their configured element in the empty
+/// cells next to them, below first for anything that falls and above first for gases.
+pub struct Source;
+
+impl ParticleBehavior for Source {
+    fn update(&self, ctx: &mut ParticleContext) {
+        let Some(flow) = ctx.cell().flow else {
+            return;
+        };
+        let Some(element) = flow.element else {
+            return;
+        };
+        // Keep flowing even while nothing around changes
+        ctx.stay_awake();
+
+        let first = match ctx.elements().get(element).element_type {
+            ElementType::Gas => (0, 1),
+            _ => (0, -1),
+        };
+        let mut sides = [(-1, 0), (1, 0), (0, -first.1)];
+        sides.shuffle(ctx.rng());
+        let count = flow.roll(ctx.rng());
+        let empty: Vec<(isize, isize)> = std::iter::once(first)
+            .chain(sides)
+            .filter(|&(dx, dy)| ctx.is_empty(dx, dy))
+            .take(count)
+            .collect();
+        for (dx, dy) in empty {
+            ctx.spawn(dx, dy, element);
+        }
+    }
+}
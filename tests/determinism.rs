@@ -0,0 +1,92 @@
+use rust_sandbox::simulation::{Boundaries, Boundary, Cell, Flow, Simulation};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+const STEPS: usize = 300;
+
+fn place(simulation: &mut Simulation, x: usize, y: usize, name: &str) {
+    let id = simulation.elements().id(name).unwrap();
+    let cell = Cell::new(simulation.elements(), id, [255; 4]);
+    simulation.set_cell(x, y, Some(cell));
+}
+
+/// Falling sand, liquids of different densities and stone.
+fn blocks(simulation: &mut Simulation) {
+    for (i, name) in ["Sand", "Water", "Oil", "Stone"].into_iter().enumerate() {
+        for x in 0..40 {
+            for y in 100..130 {
+                place(simulation, 10 + i * 45 + x, y, name);
+            }
+        }
+    }
+}
+
+/// Edges wrapping on both axes, sources pouring sand and water across them, a burning stack
+/// of wood and a stone overhang about to crumble.
+fn machinery(simulation: &mut Simulation) {
+    simulation.set_boundaries(Boundaries::all(Boundary::Wrap));
+    for (x, name) in [(0, "Sand"), (199, "Water"), (100, "Oil")] {
+        place(simulation, x, 140, "Source");
+        let element = simulation.elements().id(name).ok();
+        simulation.set_flow(x, 140, Flow { element, rate: 2.0 });
+    }
+    for y in 20..60 {
+        place(simulation, 60, y, "Stone");
+    }
+    for x in 61..75 {
+        place(simulation, x, 59, "Stone");
+    }
+    for x in 120..140 {
+        for y in 0..20 {
+            place(simulation, x, y, "Wood");
+        }
+    }
+    place(simulation, 130, 20, "Fire");
+}
+
+/// Runs a world set up by `setup` for [`STEPS`] ticks on `threads` threads and hashes every
+/// cell of the result.
+fn run(seed: u64, threads: usize, setup: fn(&mut Simulation)) -> u64 {
+    let mut simulation = Simulation::new(200, 150, seed);
+    simulation.set_threads(threads);
+    setup(&mut simulation);
+    for _ in 0..STEPS {
+        simulation.step();
+    }
+
+    let grid = simulation.grid();
+    let mut hasher = DefaultHasher::new();
+    for y in 0..grid.height() {
+        for x in 0..grid.width() {
+            match grid.get(x, y) {
+                Some(cell) => {
+                    cell.element.hash(&mut hasher);
+                    cell.color.hash(&mut hasher);
+                    cell.temperature.to_bits().hash(&mut hasher);
+                    cell.velocity.x.to_bits().hash(&mut hasher);
+                    cell.velocity.y.to_bits().hash(&mut hasher);
+                }
+                None => 0u8.hash(&mut hasher),
+            }
+        }
+    }
+    hasher.finish()
+}
+
+#[test]
+fn threads_do_not_change_the_outcome() {
+    for seed in [7, 42] {
+        assert_eq!(run(seed, 1, blocks), run(seed, 4, blocks), "seed {seed}");
+    }
+}
+
+#[test]
+fn threads_do_not_change_the_outcome_across_wrapping_edges() {
+    for seed in [7, 42] {
+        assert_eq!(
+            run(seed, 1, machinery),
+            run(seed, 4, machinery),
+            "seed {seed}"
+        );
+    }
+}
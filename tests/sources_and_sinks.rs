@@ -0,0 +1,89 @@
+use rust_sandbox::simulation::{Cell, Flow, Simulation};
+
+fn count(simulation: &Simulation, name: &str) -> usize {
+    let id = simulation.elements().id(name).unwrap();
+    let grid = simulation.grid();
+    (0..grid.height())
+        .flat_map(|y| (0..grid.width()).map(move |x| (x, y)))
+        .filter(|&(x, y)| grid.get(x, y).is_some_and(|cell| cell.element == id))
+        .count()
+}
+
+fn place(simulation: &mut Simulation, x: usize, y: usize, name: &str) {
+    let id = simulation.elements().id(name).unwrap();
+    let cell = Cell::new(simulation.elements(), id, [255; 4]);
+    simulation.set_cell(x, y, Some(cell));
+}
+
+/// A row of sinks along the bottom of the world deleting `element`, buried under sand on the
+/// left and water on the right.
+fn drain(element: Option<&str>) -> Simulation {
+    let mut simulation = Simulation::new(32, 32, 1);
+    let flow = Flow {
+        element: element.map(|name| simulation.elements().id(name).unwrap()),
+        rate: Flow::MAX_RATE,
+    };
+    for x in 0..32 {
+        place(&mut simulation, x, 0, "Sink");
+        simulation.set_flow(x, 0, flow);
+        for y in 1..6 {
+            place(&mut simulation, x, y, if x < 16 { "Sand" } else { "Water" });
+        }
+    }
+    for _ in 0..200 {
+        simulation.step();
+    }
+    simulation
+}
+
+#[test]
+fn source_creates_its_element_at_its_rate() {
+    let mut simulation = Simulation::new(32, 32, 1);
+    place(&mut simulation, 16, 28, "Source");
+    let water = simulation.elements().id("Water").unwrap();
+    simulation.set_flow(
+        16,
+        28,
+        Flow {
+            element: Some(water),
+            rate: 1.0,
+        },
+    );
+
+    for _ in 0..20 {
+        simulation.step();
+    }
+    let created = count(&simulation, "Water");
+    assert!((1..=20).contains(&created), "created {created}");
+}
+
+#[test]
+fn source_without_an_element_creates_nothing() {
+    let mut simulation = Simulation::new(32, 32, 1);
+    place(&mut simulation, 16, 28, "Source");
+    for _ in 0..20 {
+        simulation.step();
+    }
+    let grid = simulation.grid();
+    let particles = (0..grid.height())
+        .flat_map(|y| (0..grid.width()).map(move |x| (x, y)))
+        .filter(|&(x, y)| !grid.is_empty(x, y))
+        .count();
+    assert_eq!(particles, 1);
+}
+
+#[test]
+fn sink_deletes_only_its_element() {
+    let simulation = drain(Some("Sand"));
+    assert_eq!(count(&simulation, "Sand"), 0);
+    assert_eq!(count(&simulation, "Water"), 16 * 5);
+    assert_eq!(count(&simulation, "Sink"), 32);
+}
+
+#[test]
+fn sink_without_an_element_deletes_anything_but_sinks() {
+    let simulation = drain(None);
+    assert_eq!(count(&simulation, "Sand"), 0);
+    assert_eq!(count(&simulation, "Water"), 0);
+    assert_eq!(count(&simulation, "Sink"), 32);
+}
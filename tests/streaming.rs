@@ -0,0 +1,98 @@
+use rust_sandbox::simulation::{Cell, ChunkStore, Flow, Simulation};
+use std::path::PathBuf;
+
+/// Element, colour, temperature and flow of every cell, row by row.
+type Snapshot = Vec<Option<(u16, [u8; 4], u32, Option<Flow>)>>;
+
+fn snapshot(simulation: &Simulation) -> Snapshot {
+    let grid = simulation.grid();
+    (0..grid.height())
+        .flat_map(|y| (0..grid.width()).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            grid.get(x, y).map(|cell| {
+                let temperature = cell.temperature.to_bits();
+                (cell.element.0, cell.color, temperature, cell.flow)
+            })
+        })
+        .collect()
+}
+
+/// A window of 4 by 3 chunks with sand, water, stone and a source scattered over it.
+fn world() -> Simulation {
+    let mut simulation = Simulation::new(128, 96, 1);
+    let names = ["Sand", "Water", "Stone", "Source"];
+    for y in (0..96).step_by(3) {
+        for x in (0..128).step_by(5) {
+            let name = names[(x + y) % names.len()];
+            let id = simulation.elements().id(name).unwrap();
+            let cell = Cell {
+                temperature: (x * y) as f32,
+                ..Cell::new(simulation.elements(), id, [x as u8, y as u8, 7, 255])
+            };
+            simulation.set_cell(x, y, Some(cell));
+        }
+    }
+    let water = simulation.elements().id("Water").unwrap();
+    simulation.set_flow(
+        0,
+        0,
+        Flow {
+            element: Some(water),
+            rate: 0.5,
+        },
+    );
+    simulation
+}
+
+fn directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("streaming-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    directory
+}
+
+fn round_trip(mut store: ChunkStore) {
+    let mut simulation = world();
+    let before = snapshot(&simulation);
+
+    simulation.shift(3, -2, &mut store).unwrap();
+    assert_ne!(snapshot(&simulation), before);
+    simulation.shift(-3, 2, &mut store).unwrap();
+    assert_eq!(snapshot(&simulation), before);
+}
+
+#[test]
+fn shifting_away_and_back_keeps_the_world_in_memory() {
+    round_trip(ChunkStore::in_memory());
+}
+
+#[test]
+fn shifting_away_and_back_keeps_the_world_on_disk() {
+    let directory = directory("shift");
+    round_trip(ChunkStore::on_disk(directory.clone()));
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn unvisited_chunks_start_out_empty() {
+    let mut simulation = world();
+    simulation
+        .shift(10, 0, &mut ChunkStore::in_memory())
+        .unwrap();
+    assert!(snapshot(&simulation).iter().all(Option::is_none));
+}
+
+#[test]
+fn saved_window_is_picked_up_by_a_later_session() {
+    let directory = directory("session");
+    let simulation = world();
+    simulation
+        .save_window(&mut ChunkStore::on_disk(directory.clone()))
+        .unwrap();
+
+    let mut later = Simulation::new(128, 96, 2);
+    later
+        .load_window(&mut ChunkStore::on_disk(directory.clone()))
+        .unwrap();
+    assert_eq!(snapshot(&later), snapshot(&simulation));
+    std::fs::remove_dir_all(directory).unwrap();
+}